@@ -1,5 +1,5 @@
 use std::time::Duration;
-use txrx::traits::{Receiver, Scheduler, Sender};
+use txrx::traits::{Channel, Receiver, Scheduler, Sender};
 use txrx::SenderExt;
 
 #[derive(Clone)]
@@ -15,25 +15,31 @@ impl Scheduler for AsyncStdScheduler {
 
 impl Sender for AsyncStdScheduler {
     type Output = ();
+    type Error = txrx::Error;
     type Scheduler = Self;
 
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         async_std::task::spawn(async move {
             receiver.set_value(());
         });
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        Self
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(Self)
     }
 }
 
-fn build_work<Sched>(mut scheduler: Sched, time: u64, id: i32) -> impl Sender<Output = i32>
+fn build_work<Sched>(
+    mut scheduler: Sched,
+    time: u64,
+    id: i32,
+) -> impl Sender<Output = i32, Error = txrx::Error>
 where
     Sched: Scheduler,
+    Sched::Sender: Sender<Error = txrx::Error>,
 {
     scheduler.schedule().map(move |_| {
         println!("Rayon {}: Starting some intensive work!", id);
@@ -1,4 +1,4 @@
-use txrx::traits::{Receiver, Scheduler, Sender, SenderExt};
+use txrx::traits::{Channel, Receiver, Scheduler, Sender, SenderExt};
 
 // This is a scheduler that always spins up a new thread to start its work on.
 #[derive(Copy, Clone)]
@@ -6,17 +6,18 @@ struct NewThreadScheduler;
 
 impl Sender for NewThreadScheduler {
     type Output = ();
+    type Error = txrx::Error;
     type Scheduler = Self;
 
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         std::thread::spawn(move || receiver.set_value(()));
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        Self
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(Self)
     }
 }
 
@@ -18,7 +18,7 @@ impl FibonacciMatrix {
     }
 
     fn multiply_assign(&mut self, other: &Self) {
-        let old = self.clone();
+        let old = *self;
         let lhs = &mut self.data;
         lhs[0][0] = old.get(0, 0) * other.get(0, 0) + old.get(0, 1) * other.get(1, 0);
         lhs[0][1] = old.get(0, 0) * other.get(0, 1) + old.get(0, 1) * other.get(1, 1);
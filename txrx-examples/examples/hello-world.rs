@@ -2,9 +2,7 @@ use txrx::traits::Scheduler;
 use txrx::SenderExt;
 
 fn start_runner(runner: txrx::manual_executor::Runner) {
-    std::thread::spawn(move || loop {
-        runner.run_one();
-    });
+    std::thread::spawn(move || runner.run());
 }
 
 fn main() {
@@ -16,6 +16,7 @@ impl<T> MutView<T> {
         }
     }
 
+    #[allow(clippy::mut_from_ref)]
     unsafe fn get_mut_slice(&self, range: Range<usize>) -> &mut [T] {
         std::slice::from_raw_parts_mut(self.pointer.add(range.start), range.len())
     }
@@ -48,7 +49,7 @@ fn timing_end(begin: std::time::Instant) {
 }
 
 fn bulk_range(tile_count: usize, step: usize, data_len: usize) -> Range<usize> {
-    let tile_size = (data_len + tile_count - 1) / tile_count;
+    let tile_size = data_len.div_ceil(tile_count);
     let start = step * tile_size;
     let end = data_len.min((step + 1) * tile_size);
     start..end
@@ -87,7 +88,7 @@ fn main() {
                     let slice = unsafe { view.get_mut_slice(0..view.len()) };
                     let my_partial = slice[prev_range.last().unwrap()];
                     let my_last = &mut slice[range.last().unwrap()];
-                    *my_last = my_partial + *my_last;
+                    *my_last += my_partial;
                 }
             }
             view
@@ -99,10 +100,10 @@ fn main() {
                 if !range.is_empty() && !prev_range.is_empty() {
                     let slice = unsafe { view.get_mut_slice(0..view.len()) };
                     let my_partial = slice[prev_range.last().unwrap()];
-                    (&mut slice[range.start..range.end - 1])
+                    slice[range.start..range.end - 1]
                         .iter_mut()
                         .for_each(|item| {
-                            *item = my_partial + *item;
+                            *item += my_partial;
                         });
                 }
             }
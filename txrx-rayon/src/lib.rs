@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use txrx::traits::{Receiver, Scheduler, Sender, Work};
+use txrx::traits::{Channel, Receiver, Scheduler, Sender, Work};
 
 pub use rayon;
 
@@ -17,20 +17,25 @@ impl PoolScheduler {
 
 impl Sender for PoolScheduler {
     type Output = ();
+    type Error = txrx::Error;
     type Scheduler = Self;
 
     #[inline]
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         self.pool.spawn(move || {
-            receiver.set_value(());
+            if receiver.get_stop_token().stop_requested() {
+                receiver.set_cancelled();
+            } else {
+                receiver.set_value(());
+            }
         });
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        self.clone()
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(self.clone())
     }
 }
 
@@ -74,20 +79,25 @@ impl Default for GlobalScheduler {
 
 impl Sender for GlobalScheduler {
     type Output = ();
+    type Error = txrx::Error;
     type Scheduler = Self;
 
     #[inline]
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         rayon::spawn(move || {
-            receiver.set_value(());
+            if receiver.get_stop_token().stop_requested() {
+                receiver.set_cancelled();
+            } else {
+                receiver.set_value(());
+            }
         })
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        Self
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(Self)
     }
 }
 
@@ -109,3 +119,36 @@ impl Scheduler for GlobalScheduler {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PoolScheduler;
+    use std::sync::{mpsc, Arc};
+    use txrx::consumers::sync_wait::sync_wait_with_stop;
+    use txrx::stop_token::StopSource;
+    use txrx::traits::Scheduler;
+    use txrx::SenderExt;
+
+    #[test]
+    fn stop_cancels_queued_work() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let mut scheduler = PoolScheduler::new(Arc::new(pool));
+
+        // Keep the only pool thread busy so the scheduled work stays queued.
+        let (unblock, blocked) = mpsc::channel::<()>();
+        scheduler.execute(move || blocked.recv().unwrap());
+
+        let source = StopSource::new();
+        let waiter = std::thread::spawn({
+            let sender = scheduler.schedule().map(|_| 1);
+            let token = source.token();
+            move || sync_wait_with_stop(sender, token)
+        });
+        source.request_stop();
+        unblock.send(()).unwrap();
+        assert!(waiter.join().unwrap().is_cancelled());
+    }
+}
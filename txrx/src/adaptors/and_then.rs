@@ -1,5 +1,11 @@
-use crate::traits::{Receiver, Sender};
-use std::marker::PhantomData;
+use crate::adaptors::map_error::{self, MapError};
+use crate::env::Env;
+use crate::stop_token::StopToken;
+use crate::traits::{Channel, Connect, OperationState, Receiver, Sender};
+use crate::utility::PanicGuard;
+use core::cell::UnsafeCell;
+use core::marker::{PhantomData, PhantomPinned};
+use core::pin::Pin;
 
 pub struct AndThen<Input, Func> {
     input: Input,
@@ -17,33 +23,38 @@ where
     Input: Sender,
     Func: 'static + Send + FnOnce(Input::Output) -> NextSender,
     NextSender: Sender,
+    NextSender::Error: Into<Input::Error>,
 {
     type Output = NextSender::Output;
+    type Error = Input::Error;
     type Scheduler = Input::Scheduler;
 
     #[inline]
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         self.input.start(AndThenReceiver::new(self.func, receiver));
     }
 
-    #[inline]
-    fn get_scheduler(&self) -> Self::Scheduler {
-        self.input.get_scheduler()
+    // The value and the errors of the returned sender are sent from wherever that sender
+    // completes, so the completion scheduler is unknown for all channels.
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        None
     }
 }
 
 pub struct AndThenReceiver<Input, Func, NextReceiver> {
     next: NextReceiver,
     func: Func,
+    guard: PanicGuard,
     _ph: PhantomData<Input>,
 }
 
-impl<Input, Func, NextReceiver> AndThenReceiver<Input, Func, NextReceiver> {
+impl<Input, Func, NextReceiver: Receiver> AndThenReceiver<Input, Func, NextReceiver> {
     fn new(func: Func, next: NextReceiver) -> Self {
         Self {
+            guard: PanicGuard::new(&next.get_env()),
             next,
             func,
             _ph: PhantomData,
@@ -55,18 +66,24 @@ impl<Input, Func, NextReceiver, Ret> Receiver for AndThenReceiver<Input, Func, N
 where
     Func: FnOnce(Input) -> Ret,
     Ret: Sender,
+    Ret::Error: Into<NextReceiver::Error>,
     NextReceiver: 'static + Send + Receiver<Input = Ret::Output>,
+    NextReceiver::Error: 'static + Send,
     Input: 'static + Send,
 {
     type Input = Input;
+    type Error = NextReceiver::Error;
 
     #[inline]
     fn set_value(self, value: Self::Input) {
-        (self.func)(value).start(self.next);
+        let func = self.func;
+        if let Some(sender) = self.guard.call(move || func(value)) {
+            MapError::new(sender, Into::into).start(self.next);
+        }
     }
 
     #[inline]
-    fn set_error(self, error: crate::Error) {
+    fn set_error(self, error: Self::Error) {
         self.next.set_error(error);
     }
 
@@ -74,4 +91,161 @@ where
     fn set_cancelled(self) {
         self.next.set_cancelled();
     }
+
+    #[inline]
+    fn get_stop_token(&self) -> StopToken {
+        self.next.get_stop_token()
+    }
+
+    #[inline]
+    fn get_env(&self) -> Env {
+        self.next.get_env()
+    }
+}
+
+/// Receiver for the sender returned by the function, converts its errors using `Into`.
+pub type SecondReceiver<Next, R> = map_error::Receiver<
+    <Next as Sender>::Error,
+    R,
+    fn(<Next as Sender>::Error) -> <R as Receiver>::Error,
+>;
+
+type SecondOperation<Next, R> = <Next as Connect<SecondReceiver<Next, R>>>::Operation;
+type FirstOperation<Input, Func, Next, R> = <Input as Connect<
+    FirstReceiver<<Input as Sender>::Output, Func, R, SecondOperation<Next, R>>,
+>>::Operation;
+
+impl<Input, Func, Next, R> Connect<R> for AndThen<Input, Func>
+where
+    Input: Sender + Connect<FirstReceiver<Input::Output, Func, R, SecondOperation<Next, R>>>,
+    Func: FnOnce(Input::Output) -> Next,
+    Next: Sender + Connect<SecondReceiver<Next, R>>,
+    Next::Error: Into<R::Error>,
+    R: Receiver<Input = Next::Output>,
+{
+    type Operation = AndThenOperation<Input, Func, Next, R>;
+
+    #[inline]
+    fn connect(self, receiver: R) -> Self::Operation {
+        AndThenOperation {
+            pending: Some((self.input, self.func, receiver)),
+            first: None,
+            second: UnsafeCell::new(None),
+            _pinned: PhantomPinned,
+        }
+    }
+}
+
+/// Operation state of [`AndThen`]. The operation state of the sender returned by the function is
+/// stored inline, next to the one of the input sender.
+pub struct AndThenOperation<Input, Func, Next, R>
+where
+    Input: Sender + Connect<FirstReceiver<Input::Output, Func, R, SecondOperation<Next, R>>>,
+    Next: Sender + Connect<SecondReceiver<Next, R>>,
+    R: Receiver,
+{
+    // The input is connected when started, once the address of `second` is fixed.
+    pending: Option<(Input, Func, R)>,
+    first: Option<FirstOperation<Input, Func, Next, R>>,
+    second: UnsafeCell<Option<SecondOperation<Next, R>>>,
+    _pinned: PhantomPinned,
+}
+
+impl<Input, Func, Next, R> OperationState for AndThenOperation<Input, Func, Next, R>
+where
+    Input: Sender + Connect<FirstReceiver<Input::Output, Func, R, SecondOperation<Next, R>>>,
+    Next: Sender + Connect<SecondReceiver<Next, R>>,
+    R: Receiver,
+{
+    unsafe fn start(self: Pin<&mut Self>) {
+        let this = self.get_unchecked_mut();
+        if let Some((input, func, next)) = this.pending.take() {
+            let receiver = FirstReceiver {
+                func,
+                guard: PanicGuard::new(&next.get_env()),
+                next,
+                second: this.second.get(),
+                _phantom: PhantomData,
+            };
+            let first = this.first.insert(input.connect(receiver));
+            // Safety: `first` is structurally pinned, `self` is.
+            Pin::new_unchecked(first).start();
+        }
+    }
+}
+
+/// Receiver for the input sender in an [`AndThenOperation`].
+pub struct FirstReceiver<Input, Func, R, SecondOp> {
+    func: Func,
+    next: R,
+    guard: PanicGuard,
+    // Points into the pinned `AndThenOperation` that owns the operation this receiver belongs to.
+    second: *mut Option<SecondOp>,
+    _phantom: PhantomData<fn(Input)>,
+}
+
+// Safety: `second` is only written once, by the receiver, and owned by the operation state.
+unsafe impl<Input, Func: Send, R: Send, SecondOp: Send> Send
+    for FirstReceiver<Input, Func, R, SecondOp>
+{
+}
+
+impl<Input, Func, Next, R, SecondOp> Receiver for FirstReceiver<Input, Func, R, SecondOp>
+where
+    Func: FnOnce(Input) -> Next,
+    Next: Sender + Connect<SecondReceiver<Next, R>, Operation = SecondOp>,
+    Next::Error: Into<R::Error>,
+    R: Receiver<Input = Next::Output>,
+    SecondOp: OperationState,
+{
+    type Input = Input;
+    type Error = R::Error;
+
+    fn set_value(self, value: Self::Input) {
+        let func = self.func;
+        if let Some(sender) = self.guard.call(move || func(value)) {
+            let receiver = SecondReceiver::<Next, R>::new(self.next, Into::into);
+            // Safety: the operation state that owns `second` is pinned and outlives this
+            // receiver, see `OperationState::start()`.
+            unsafe {
+                let second = (*self.second).insert(sender.connect(receiver));
+                Pin::new_unchecked(second).start();
+            }
+        }
+    }
+
+    fn set_error(self, error: Self::Error) {
+        self.next.set_error(error);
+    }
+
+    fn set_cancelled(self) {
+        self.next.set_cancelled();
+    }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.next.get_stop_token()
+    }
+
+    fn get_env(&self) -> Env {
+        self.next.get_env()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manual_executor::ManualExecutor;
+    use crate::test::Stoppable;
+    use crate::traits::Scheduler;
+    use crate::SenderExt;
+
+    #[test]
+    fn forwards_stop_requests_to_returned_sender() {
+        let executor = ManualExecutor::new();
+        let mut scheduler = executor.scheduler();
+        let sender = crate::just(()).and_then(move |_| scheduler.schedule());
+        let started = Stoppable::start(sender);
+        started.request_stop();
+        assert!(executor.runner().run_one());
+        assert!(started.take().unwrap().is_cancelled());
+    }
 }
@@ -1,9 +1,12 @@
+use crate::env::{Env, PreferredScheduler};
 use crate::priv_sync::Mutex;
-use crate::traits::{Receiver, Sender};
-use crate::utility::UnsafeSyncCell;
-use std::marker::PhantomData;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use crate::stop_token::StopToken;
+use crate::traits::{Channel, Receiver, Sender, Work};
+use crate::utility::{PanicGuard, UnsafeSyncCell};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 /// Sender for invoking a function with the values sent by the input sender multiple times.
 /// See [`bulk()`] for details.
@@ -14,12 +17,27 @@ pub struct Bulk<Input, Func> {
     input: Input,
     size: usize,
     func: Func,
+    catch_unwind: bool,
 }
 
 impl<Input, Func> Bulk<Input, Func> {
     #[inline]
     pub fn new(input: Input, size: usize, func: Func) -> Self {
-        Self { input, size, func }
+        Self {
+            input,
+            size,
+            func,
+            catch_unwind: false,
+        }
+    }
+
+    /// Catches panics in the bulk function if there is a
+    /// [`catch_unwind()`](crate::SenderExt::catch_unwind) sender downstream, see
+    /// [`bulk_catch_unwind()`](crate::SenderExt::bulk_catch_unwind).
+    #[inline]
+    pub fn catching_panics(mut self) -> Self {
+        self.catch_unwind = true;
+        self
     }
 }
 
@@ -31,26 +49,33 @@ where
     BulkOutput: 'static + Send,
 {
     type Output = (Input::Output, Vec<BulkOutput>);
+    type Error = Input::Error;
     type Scheduler = Input::Scheduler;
 
     #[inline]
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
-        let scheduler = self.input.get_scheduler();
+        let scheduler = self.input.get_completion_scheduler(Channel::Value);
+        // Only looked up when asked to, so that the items don't have to.
+        let guard = match self.catch_unwind {
+            true => PanicGuard::new(&receiver.get_env()),
+            false => PanicGuard::default(),
+        };
         self.input.start(BulkReceiver::new(
             scheduler,
             receiver,
             self.size,
             self.func,
+            guard,
             PhantomData::<Input::Output>,
         ))
     }
 
     #[inline]
-    fn get_scheduler(&self) -> Self::Scheduler {
-        self.input.get_scheduler()
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        self.input.get_completion_scheduler(channel)
     }
 }
 
@@ -58,10 +83,12 @@ struct BulkReceiver<Scheduler, InputData, NextReceiver, Func, BulkOutput>
 where
     Func: Fn(usize, &InputData) -> BulkOutput,
 {
-    scheduler: Scheduler,
+    // The functions are invoked inline if the scheduler is unknown.
+    scheduler: Option<Scheduler>,
     next_receiver: NextReceiver,
     bulk_function: Func,
     size: usize,
+    guard: PanicGuard,
     _ph: PhantomData<InputData>,
 }
 
@@ -72,10 +99,11 @@ where
 {
     #[inline]
     fn new(
-        scheduler: Scheduler,
+        scheduler: Option<Scheduler>,
         next_receiver: NextReceiver,
         size: usize,
         bulk_function: Func,
+        guard: PanicGuard,
         _ph: PhantomData<InputData>,
     ) -> Self {
         Self {
@@ -83,11 +111,71 @@ where
             next_receiver,
             bulk_function,
             size,
+            guard,
             _ph,
         }
     }
 }
 
+impl<Scheduler, InputData, NextReceiver, Func, BulkOutput>
+    BulkReceiver<Scheduler, InputData, NextReceiver, Func, BulkOutput>
+where
+    InputData: 'static + Send + Sync,
+    Func: 'static + Clone + Send + Fn(usize, &InputData) -> BulkOutput,
+    NextReceiver: 'static + Send + Receiver<Input = (InputData, Vec<BulkOutput>)>,
+    BulkOutput: 'static + Send,
+{
+    // Runs all but the last function on `scheduler` and the last one inline. `size` is not zero.
+    fn run_on<S: crate::traits::Scheduler>(self, mut scheduler: S, value: InputData) {
+        let mut result_slots: Vec<Option<BulkOutput>> = Vec::with_capacity(self.size);
+        result_slots.resize_with(self.size, || None);
+
+        // Safety: we move result_slots to the WorkEndBarrier, but the pointer obtained to the'
+        // underlying data is alive for as long as end_barrier is alive.
+        let result_slots_ptr = result_slots.as_mut_ptr();
+        let end_barrier = WorkEndBarrier::new(self.size, value, self.next_receiver, result_slots);
+
+        for x in 0..self.size - 1 {
+            let end_barrier = end_barrier.clone();
+            // Safety:
+            //   We get a mut reference based on the bulk functions index. We never obtain
+            // multiple mut references to each result slot, only to different slots.
+            //
+            // The pointer is guaranteed to stay alive until end_barrier.signal() has been called
+            // size times and since the scheduler either executes or cancels each item, consuming
+            // it, we know that each function will only be invoked once.
+            let result_slot = unsafe { &mut *(result_slots_ptr.add(x)) };
+            scheduler.execute(BulkItem {
+                index: x,
+                result_slot,
+                bulk_function: self.bulk_function.clone(),
+                end_barrier,
+                guard: self.guard.clone(),
+            });
+        }
+
+        // See discussion on safety inside for loop.
+        let result_slot = unsafe { &mut *(result_slots_ptr.add(self.size - 1)) };
+        let bulk_func = self.bulk_function;
+        let last = self.size - 1;
+        *result_slot = self
+            .guard
+            .call(|| bulk_func(last, unsafe { end_barrier.input_data() }));
+        end_barrier.signal();
+    }
+
+    fn run_inline(self, value: InputData) {
+        let results = self.guard.call(|| {
+            (0..self.size)
+                .map(|x| (self.bulk_function)(x, &value))
+                .collect()
+        });
+        if let Some(results) = results {
+            self.next_receiver.set_value((value, results));
+        }
+    }
+}
+
 impl<Scheduler, InputData, NextReceiver, Func, BulkOutput> Receiver
     for BulkReceiver<Scheduler, InputData, NextReceiver, Func, BulkOutput>
 where
@@ -98,56 +186,33 @@ where
     BulkOutput: 'static + Send,
 {
     type Input = InputData;
+    type Error = NextReceiver::Error;
 
     #[inline]
     fn set_value(mut self, value: Self::Input) {
-        if self.size != 0 {
-            let mut result_slots: Vec<Option<BulkOutput>> = Vec::with_capacity(self.size);
-            result_slots.resize_with(self.size, || None);
-
-            // Safety: we move result_slots to the WorkEndBarrier, but the pointer obtained to the'
-            // underlying data is alive for as long as end_barrier is alive.
-            let result_slots_ptr = result_slots.as_mut_ptr();
-            let end_barrier =
-                WorkEndBarrier::new(self.size, value, self.next_receiver, result_slots);
-
-            for x in 0..self.size - 1 {
-                let end_barrier = end_barrier.clone();
-                // Safety:
-                //   We get a mut reference based on the bulk functions index. We never obtain
-                // multiple mut references to each result slot, only to different slots.
-                //
-                // The pointer is guaranteed to stay alive until end_barrier.signal() has been called
-                // size times and since execute takes FnOnce implementations we know that each function
-                // will only be invoked once.
-                let result_slot = unsafe { &mut *(result_slots_ptr.add(x)) };
-                let bulk_func = self.bulk_function.clone();
-                self.scheduler.execute(move || {
-                    // Safety:
-                    //   input_data is Sync, so it's valid to read the data accross threads.
-                    // We only form shared references to input data unless all functions have signaled
-                    // that they are complete. And since this function hasn't signaled completion yet,
-                    // we are good to go.
-                    let input_data = unsafe { end_barrier.input_data() };
-
-                    *result_slot = Some(bulk_func(x, input_data));
-                    end_barrier.signal();
-                });
-            }
-
-            // See discussion on safety inside for loop.
-            let result_slot = unsafe { &mut *(result_slots_ptr.add(self.size - 1)) };
-            *result_slot = Some((self.bulk_function)(self.size - 1, unsafe {
-                end_barrier.input_data()
-            }));
-            end_barrier.signal();
-        } else {
-            self.next_receiver.set_value((value, Vec::new()));
+        if self.next_receiver.get_stop_token().stop_requested() {
+            self.next_receiver.set_cancelled();
+            return;
+        }
+        // Without a completion scheduler on the input, fall back to the scheduler the consumer
+        // made available in the environment, see `PreferredScheduler`.
+        match self.scheduler.take() {
+            _ if self.size == 0 => self.run_inline(value),
+            Some(scheduler) => self.run_on(scheduler, value),
+            None => match self
+                .next_receiver
+                .get_env()
+                .get::<PreferredScheduler>()
+                .map(PreferredScheduler::get)
+            {
+                Some(scheduler) => self.run_on(scheduler, value),
+                None => self.run_inline(value),
+            },
         }
     }
 
     #[inline]
-    fn set_error(self, error: crate::Error) {
+    fn set_error(self, error: Self::Error) {
         self.next_receiver.set_error(error);
     }
 
@@ -155,11 +220,57 @@ where
     fn set_cancelled(self) {
         self.next_receiver.set_cancelled();
     }
+
+    #[inline]
+    fn get_stop_token(&self) -> StopToken {
+        self.next_receiver.get_stop_token()
+    }
+
+    #[inline]
+    fn get_env(&self) -> Env {
+        self.next_receiver.get_env()
+    }
+}
+
+// One invocation of the bulk function, run on the scheduler.
+struct BulkItem<InputData, BulkResult: 'static, Next, Func> {
+    index: usize,
+    result_slot: &'static mut Option<BulkResult>,
+    bulk_function: Func,
+    end_barrier: Arc<WorkEndBarrier<InputData, BulkResult, Next>>,
+    guard: PanicGuard,
+}
+
+impl<InputData, BulkResult: 'static, Next, Func> Work
+    for BulkItem<InputData, BulkResult, Next, Func>
+where
+    Func: Fn(usize, &InputData) -> BulkResult,
+    Next: Receiver<Input = (InputData, Vec<BulkResult>)>,
+{
+    fn execute(self) {
+        // Safety:
+        //   input_data is Sync, so it's valid to read the data accross threads.
+        // We only form shared references to input data unless all functions have signaled
+        // that they are complete. And since this function hasn't signaled completion yet,
+        // we are good to go.
+        let input_data = unsafe { self.end_barrier.input_data() };
+
+        let (index, bulk_function) = (self.index, self.bulk_function);
+        *self.result_slot = self.guard.call(|| bulk_function(index, input_data));
+        self.end_barrier.signal();
+    }
+
+    // The scheduler dropped this invocation, so the results are incomplete.
+    fn cancel(self) {
+        self.end_barrier.cancelled.store(true, Ordering::Release);
+        self.end_barrier.signal();
+    }
 }
 
 struct WorkEndBarrier<InputData, BulkResult, Next> {
     input_data: UnsafeSyncCell<Option<InputData>>,
     waiting_for: AtomicUsize,
+    cancelled: AtomicBool,
     next: Mutex<Option<(Next, Vec<Option<BulkResult>>)>>,
 }
 
@@ -177,6 +288,7 @@ where
         Arc::new(Self {
             input_data: UnsafeSyncCell::new(Some(input_data)),
             waiting_for: AtomicUsize::new(size),
+            cancelled: AtomicBool::new(false),
             next: Mutex::new(Some((next, result_slots))),
         })
     }
@@ -194,13 +306,18 @@ where
             let mut lock = self.next.lock();
 
             if let Some((next, results)) = lock.take() {
-                let results: Vec<BulkResult> = results.into_iter().map(|x| x.unwrap()).collect();
+                // A result is missing if its bulk function panicked, see `PanicGuard`.
+                let results: Option<Vec<BulkResult>> = results.into_iter().collect();
                 // Safety: We only get here once all bulk functions have completed, so no live references
                 // to input is in play.
                 let input_data = unsafe { (&mut *self.input_data.get()).take().unwrap() };
                 // drop lock so we don't run user code under lock.
                 drop(lock);
-                next.set_value((input_data, results));
+                match results {
+                    _ if self.cancelled.load(Ordering::Acquire) => next.set_cancelled(),
+                    Some(results) => next.set_value((input_data, results)),
+                    None => drop(next),
+                }
             }
         }
     }
@@ -208,12 +325,24 @@ where
 
 #[cfg(test)]
 mod tests {
+    use crate::env::PreferredScheduler;
+    use crate::manual_executor::ManualExecutor;
+    use crate::test::{ManualSender, Stoppable};
     use crate::traits::Scheduler;
     use crate::traits::SenderExt;
 
+    #[test]
+    fn stop_requested_skips_work() {
+        let (sender, trigger) = ManualSender::new();
+        let started = Stoppable::start(sender.bulk(4, |_, _| -> i32 { panic!("Never called") }));
+        started.request_stop();
+        trigger.trigger();
+        assert!(started.take().unwrap().is_cancelled());
+    }
+
     #[test]
     fn bulk_test() {
-        let executor = crate::manual_executor::ManualExecutor::new();
+        let executor = ManualExecutor::new();
         let fut = executor
             .scheduler()
             .schedule()
@@ -225,4 +354,31 @@ mod tests {
         assert!(executor.runner().run_one());
         assert!(fut.is_complete());
     }
+
+    #[test]
+    fn scheduler_from_env() {
+        let executor = ManualExecutor::new();
+        let (sender, trigger) = ManualSender::new();
+        let fut = sender
+            .bulk(2, |step, _| step)
+            .with_env(PreferredScheduler::new(executor.scheduler()))
+            .ensure_started();
+        trigger.trigger();
+        assert!(!fut.is_complete());
+        assert!(executor.runner().run_one());
+        assert_eq!(fut.sync_wait().unwrap(), ((), vec![0, 1]));
+    }
+
+    #[test]
+    fn shutdown_cancels() {
+        let executor = ManualExecutor::new();
+        let (sender, trigger) = ManualSender::new();
+        let fut = sender
+            .bulk(3, |step, _| step)
+            .with_env(PreferredScheduler::new(executor.scheduler()))
+            .ensure_started();
+        trigger.trigger();
+        executor.shutdown();
+        assert!(fut.sync_wait().is_cancelled());
+    }
 }
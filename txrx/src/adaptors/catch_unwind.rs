@@ -0,0 +1,270 @@
+use crate::env::Env;
+use crate::priv_sync::Mutex;
+use crate::stop_token::StopToken;
+use crate::traits::{Channel, Receiver, Sender};
+use std::any::Any;
+use std::marker::PhantomData;
+use std::panic::AssertUnwindSafe;
+use std::string::{String, ToString};
+use std::sync::Arc;
+
+/// Error sent by [`catch_unwind()`](crate::SenderExt::catch_unwind) when a closure in the
+/// pipeline panics.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Panicked {
+    message: String,
+}
+
+impl Panicked {
+    /// The message the closure panicked with.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::fmt::Display for Panicked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "panicked: {}", self.message)
+    }
+}
+
+impl std::error::Error for Panicked {}
+
+// Made available through the environment by `CatchUnwind`. Adaptors that invoke user closures
+// store the panic message here and drop their receiver, and `CatchUnwind` turns the dropped
+// receiver into a `Panicked` error.
+#[derive(Clone)]
+pub(crate) struct PanicSink {
+    message: Arc<Mutex<Option<String>>>,
+}
+
+impl PanicSink {
+    fn new() -> Self {
+        Self {
+            message: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn store(&self, payload: Box<dyn Any + Send>) {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "non-string panic payload".to_string()
+        };
+        // Only the first panic is reported.
+        self.message.lock().get_or_insert(message);
+    }
+
+    fn take(&self) -> Option<String> {
+        self.message.lock().take()
+    }
+
+    /// Invokes `func`, returning `None` if it panicked.
+    pub(crate) fn call<T>(&self, func: impl FnOnce() -> T) -> Option<T> {
+        match std::panic::catch_unwind(AssertUnwindSafe(func)) {
+            Ok(value) => Some(value),
+            Err(payload) => {
+                self.store(payload);
+                None
+            }
+        }
+    }
+}
+
+/// Sender that turns panics in the closures of the input sender into errors.
+/// See [`catch_unwind()`](crate::SenderExt::catch_unwind) for details.
+pub struct CatchUnwind<S> {
+    sender: S,
+}
+
+impl<S> CatchUnwind<S> {
+    pub fn new(sender: S) -> Self {
+        Self { sender }
+    }
+}
+
+impl<S> Sender for CatchUnwind<S>
+where
+    S: Sender,
+    S::Error: Into<crate::Error>,
+{
+    type Output = S::Output;
+    type Error = crate::Error;
+    type Scheduler = S::Scheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        let sink = PanicSink::new();
+        let env = receiver.get_env().with(sink.clone());
+        self.sender.start(CatchUnwindReceiver {
+            next: Some(receiver),
+            sink,
+            env,
+            _error: PhantomData::<fn(S::Error)>,
+        });
+    }
+
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        self.sender.get_completion_scheduler(channel)
+    }
+}
+
+pub struct CatchUnwindReceiver<R, E>
+where
+    R: Receiver<Error = crate::Error>,
+{
+    // Taken when the receiver is completed.
+    next: Option<R>,
+    sink: PanicSink,
+    env: Env,
+    _error: PhantomData<fn(E)>,
+}
+
+impl<R, E> Drop for CatchUnwindReceiver<R, E>
+where
+    R: Receiver<Error = crate::Error>,
+{
+    fn drop(&mut self) {
+        if let Some(next) = self.next.take() {
+            // Without a caught panic, dropping `next` passes the abandonment on.
+            if let Some(message) = self.sink.take() {
+                next.set_error(Box::new(Panicked { message }));
+            }
+        }
+    }
+}
+
+impl<R, E> Receiver for CatchUnwindReceiver<R, E>
+where
+    R: Receiver<Error = crate::Error>,
+    E: Into<crate::Error>,
+{
+    type Input = R::Input;
+    type Error = E;
+
+    fn set_value(mut self, value: Self::Input) {
+        if let Some(next) = self.next.take() {
+            next.set_value(value);
+        }
+    }
+
+    fn set_error(mut self, error: Self::Error) {
+        if let Some(next) = self.next.take() {
+            next.set_error(error.into());
+        }
+    }
+
+    fn set_cancelled(mut self) {
+        if let Some(next) = self.next.take() {
+            next.set_cancelled();
+        }
+    }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.next
+            .as_ref()
+            .map(|next| next.get_stop_token())
+            .unwrap_or_default()
+    }
+
+    fn get_env(&self) -> Env {
+        self.env.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Panicked;
+    use crate::test::ManualSender;
+    use crate::SenderExt;
+
+    fn panic_message(error: crate::Error) -> String {
+        error
+            .downcast_ref::<Panicked>()
+            .unwrap()
+            .message()
+            .to_string()
+    }
+
+    #[test]
+    fn map_and_then() {
+        let error = crate::just(1)
+            .map(|_| -> i32 { panic!("map failed") })
+            .catch_unwind()
+            .sync_wait()
+            .unwrap_error();
+        assert_eq!(panic_message(error), "map failed");
+
+        // Panics on another thread, after the pipeline has been started.
+        let (sender, trigger) = ManualSender::new();
+        let sender = sender
+            .and_then(|_| -> crate::factories::just_sender::Just<i32> {
+                panic!("and_then failed: {}", 1)
+            })
+            .catch_unwind()
+            .ensure_started();
+        std::thread::spawn(move || trigger.trigger())
+            .join()
+            .unwrap();
+        let error = sender.sync_wait().unwrap_error();
+        assert_eq!(panic_message(error), "and_then failed: 1");
+
+        assert_eq!(crate::just(1).catch_unwind().sync_wait().unwrap(), 1);
+    }
+
+    #[test]
+    fn error_channel() {
+        let error = crate::factories::error("failed")
+            .map(|_| 1)
+            .upon_error(|_| -> i32 { panic!("upon_error failed") })
+            .catch_unwind()
+            .sync_wait()
+            .unwrap_error();
+        assert_eq!(panic_message(error), "upon_error failed");
+
+        let error = crate::factories::cancelled()
+            .map(|_| 1)
+            .let_cancelled(|| -> crate::factories::just_sender::Just<i32> {
+                panic!("let_cancelled failed")
+            })
+            .catch_unwind()
+            .sync_wait()
+            .unwrap_error();
+        assert_eq!(panic_message(error), "let_cancelled failed");
+    }
+
+    #[test]
+    fn bulk() {
+        let executor = crate::manual_executor::ManualExecutor::new();
+        let runner = executor.runner();
+        let sender = crate::traits::Scheduler::schedule(&mut executor.scheduler())
+            .bulk_catch_unwind(4, |i, _| {
+                if i == 1 {
+                    panic!("bulk failed");
+                }
+                i
+            });
+        let (result, count) = runner.run_until(sender);
+        assert_eq!(panic_message(result.unwrap_error()), "bulk failed");
+        assert_eq!(count, 4);
+    }
+
+    #[test]
+    fn not_caught_before_attached() {
+        // The input of `ensure_started()` is started with an empty env, before `catch_unwind()`
+        // is attached, so the panic unwinds through the thread completing it.
+        let (sender, trigger) = ManualSender::new();
+        let sender = sender
+            .map(|_| -> i32 { panic!("map failed") })
+            .ensure_started()
+            .catch_unwind();
+        assert!(std::thread::spawn(move || trigger.trigger())
+            .join()
+            .is_err());
+        assert!(sender.sync_wait().is_abandoned());
+    }
+}
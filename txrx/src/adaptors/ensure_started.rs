@@ -1,70 +1,67 @@
 use crate::priv_sync::Mutex;
+use crate::stop_token::{StopCallback, StopSource, StopToken};
 use crate::traits::receiver::DynReceiver;
-use crate::traits::{Receiver, Sender};
-use std::sync::Arc;
+use crate::traits::{Channel, Receiver, Sender};
+use crate::utility::CompletionSchedulers;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
 
-struct InputHolderSetResult<T, R> {
-    value_and_receiver: Option<(ReceiverInput<T>, R)>,
+struct InputHolderSetResult<T, E, R> {
+    value_and_receiver: Option<(ReceiverInput<T, E>, R)>,
 }
 
-impl<T, R> InputHolderSetResult<T, R> {
+impl<T, E, R> InputHolderSetResult<T, E, R> {
     fn empty() -> Self {
         Self {
             value_and_receiver: None,
         }
     }
 
-    fn new(value: ReceiverInput<T>, receiver: R) -> Self {
+    fn new(value: ReceiverInput<T, E>, receiver: R) -> Self {
         Self {
             value_and_receiver: Some((value, receiver)),
         }
     }
 }
 
-impl<T, R: Receiver<Input = T>> InputHolderSetResult<T, R> {
+impl<T, E, R: Receiver<Input = T, Error = E>> InputHolderSetResult<T, E, R> {
     fn consume(self) {
         if let Some((value, receiver)) = self.value_and_receiver {
             match value {
                 ReceiverInput::Value(value) => receiver.set_value(value),
                 ReceiverInput::Error(error) => receiver.set_error(error),
                 ReceiverInput::Cancelled => receiver.set_cancelled(),
+                // Dropping the continuation passes the abandonment on.
+                ReceiverInput::Abandoned => drop(receiver),
             }
         }
     }
 }
 
-impl<T: 'static + Send> InputHolderSetResult<T, Box<dyn DynReceiver<Input = T>>> {
-    fn consume(self) {
-        if let Some((value, mut receiver)) = self.value_and_receiver {
-            match value {
-                ReceiverInput::Value(value) => receiver.dyn_set_value(value),
-                ReceiverInput::Error(error) => receiver.dyn_set_error(error),
-                ReceiverInput::Cancelled => receiver.dyn_set_cancelled(),
-            }
-        }
-    }
-}
-
-struct InputHolder<T> {
-    value: Option<ReceiverInput<T>>,
-    continuation: Option<Box<dyn Send + DynReceiver<Input = T>>>,
+struct InputHolder<T, E> {
+    value: Option<ReceiverInput<T, E>>,
+    continuation: Option<Box<dyn Send + DynReceiver<Input = T, Error = E>>>,
+    stop_callback: Option<StopCallback>,
 }
 
-impl<T> InputHolder<T> {
+impl<T, E> InputHolder<T, E> {
     fn new() -> Self {
         Self {
             value: None,
             continuation: None,
+            stop_callback: None,
         }
     }
 
-    fn set_continuation<R: 'static + Send + Receiver<Input = T>>(
+    fn set_continuation<R: 'static + Send + Receiver<Input = T, Error = E>>(
         &mut self,
         receiver: R,
-    ) -> InputHolderSetResult<T, R> {
+        stop_callback: StopCallback,
+    ) -> InputHolderSetResult<T, E, R> {
         if let Some(x) = self.value.take() {
             InputHolderSetResult::new(x, receiver)
         } else {
+            self.stop_callback = Some(stop_callback);
             self.continuation = Some(Box::new(crate::utility::ReceiverRef::new(receiver)));
             InputHolderSetResult::empty()
         }
@@ -72,9 +69,10 @@ impl<T> InputHolder<T> {
 
     fn set_value(
         &mut self,
-        value: ReceiverInput<T>,
-    ) -> InputHolderSetResult<T, Box<dyn DynReceiver<Input = T>>> {
+        value: ReceiverInput<T, E>,
+    ) -> InputHolderSetResult<T, E, Box<dyn DynReceiver<Input = T, Error = E>>> {
         if let Some(receiver) = self.continuation.take() {
+            self.stop_callback = None;
             InputHolderSetResult::new(value, receiver)
         } else {
             self.value = Some(value);
@@ -83,24 +81,27 @@ impl<T> InputHolder<T> {
     }
 }
 
-enum ReceiverInput<T> {
+enum ReceiverInput<T, E> {
     Value(T),
-    Error(crate::Error),
+    Error(E),
     Cancelled,
+    Abandoned,
 }
 
 struct SharedState<S: Sender> {
-    state: Mutex<InputHolder<S::Output>>,
+    state: Mutex<InputHolder<S::Output, S::Error>>,
+    stop_source: StopSource,
 }
 
 impl<S: Sender> SharedState<S> {
     fn new() -> Self {
         Self {
             state: Mutex::new(InputHolder::new()),
+            stop_source: StopSource::new(),
         }
     }
 
-    fn on_input(&self, input: ReceiverInput<S::Output>) {
+    fn on_input(&self, input: ReceiverInput<S::Output, S::Error>) {
         {
             let mut lock = self.state.lock();
             lock.set_value(input)
@@ -108,8 +109,18 @@ impl<S: Sender> SharedState<S> {
         .consume();
     }
 
-    fn on_continuation<R: 'static + Send + Receiver<Input = S::Output>>(&self, receiver: R) {
-        { self.state.lock().set_continuation(receiver) }.consume();
+    fn on_continuation<R>(&self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = S::Output, Error = S::Error>,
+    {
+        // Register outside of the lock, the callback may run immediately.
+        let stop_callback = receiver.get_stop_token().on_stop({
+            let stop_source = self.stop_source.clone();
+            move || {
+                stop_source.request_stop();
+            }
+        });
+        { self.state.lock().set_continuation(receiver, stop_callback) }.consume();
     }
 
     fn has_input(&self) -> bool {
@@ -117,40 +128,63 @@ impl<S: Sender> SharedState<S> {
     }
 }
 
+// Doesn't forward an environment, the continuation is attached after the input is started.
 pub struct ReceiverType<S: Sender>
 where
     S: Sender,
 {
     state: Arc<SharedState<S>>,
+    completed: bool,
 }
 
 impl<S: Sender> ReceiverType<S> {
     fn new() -> Self {
         Self {
             state: Arc::new(SharedState::new()),
+            completed: false,
+        }
+    }
+
+    fn complete(mut self, input: ReceiverInput<S::Output, S::Error>) {
+        self.completed = true;
+        self.state.on_input(input);
+    }
+}
+
+// If the input sender drops the receiver without completing it, the continuation is dropped as
+// well, so that abandonment is detected downstream instead of the continuation waiting forever.
+impl<S: Sender> Drop for ReceiverType<S> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.state.on_input(ReceiverInput::Abandoned);
         }
     }
 }
 
 impl<S: Sender> Receiver for ReceiverType<S> {
     type Input = S::Output;
+    type Error = S::Error;
 
     fn set_value(self, value: Self::Input) {
-        self.state.on_input(ReceiverInput::Value(value))
+        self.complete(ReceiverInput::Value(value))
     }
 
-    fn set_error(self, error: crate::Error) {
-        self.state.on_input(ReceiverInput::Error(error))
+    fn set_error(self, error: Self::Error) {
+        self.complete(ReceiverInput::Error(error))
     }
 
     fn set_cancelled(self) {
-        self.state.on_input(ReceiverInput::Cancelled);
+        self.complete(ReceiverInput::Cancelled);
+    }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
     }
 }
 
 pub struct EnsureStarted<S: Sender> {
     state: Arc<SharedState<S>>,
-    scheduler: S::Scheduler,
+    schedulers: CompletionSchedulers<S::Scheduler>,
 }
 
 impl<S: 'static + Sender> EnsureStarted<S> {
@@ -158,11 +192,11 @@ impl<S: 'static + Sender> EnsureStarted<S> {
     pub fn new(sender: S) -> Self {
         let receiver = ReceiverType::new();
         let shared_state = receiver.state.clone();
-        let scheduler = sender.get_scheduler();
+        let schedulers = CompletionSchedulers::new(&sender);
         sender.start(receiver);
         Self {
             state: shared_state,
-            scheduler,
+            schedulers,
         }
     }
 
@@ -174,23 +208,27 @@ impl<S: 'static + Sender> EnsureStarted<S> {
 
 impl<S: 'static + Sender> Sender for EnsureStarted<S> {
     type Output = S::Output;
+    type Error = S::Error;
     type Scheduler = S::Scheduler;
 
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         self.state.on_continuation(receiver);
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        self.scheduler.clone()
+    // If the input has completed by the time the continuation is attached, the continuation is
+    // completed inline. So this is only correct if the continuation is attached early enough.
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        self.schedulers.get(channel)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::test::ManualSender;
+    use crate::test::{ManualSender, Stoppable};
+    use crate::traits::Scheduler;
     use crate::SenderExt;
 
     #[test]
@@ -208,4 +246,24 @@ mod tests {
         let result = sender.sync_wait();
         assert_eq!(result.unwrap(), 23);
     }
+
+    #[test]
+    fn forwards_stop_requests() {
+        let exec = crate::manual_executor::ManualExecutor::new();
+        let sender = exec.scheduler().schedule().ensure_started();
+        // The continuation's stop request reaches the already started input.
+        let started = Stoppable::start(sender);
+        started.request_stop();
+        assert!(exec.runner().run_one());
+        assert!(started.take().unwrap().is_cancelled());
+    }
+
+    #[test]
+    fn abandoned() {
+        let (sender, trigger) = ManualSender::new();
+        let sender = sender.ensure_started();
+        drop(trigger);
+        assert!(sender.is_complete());
+        assert!(sender.map(|_| 1).sync_wait().is_abandoned());
+    }
 }
@@ -0,0 +1,92 @@
+use crate::adaptors::map_error::MapError;
+use crate::env::Env;
+use crate::stop_token::StopToken;
+use crate::traits::{Channel, Receiver, Sender};
+use crate::utility::PanicGuard;
+
+/// Sender that starts a fallback sender when the input sender is cancelled.
+/// See [`let_cancelled()`](crate::SenderExt::let_cancelled) for details.
+pub struct LetCancelled<Input, Func> {
+    input: Input,
+    func: Func,
+}
+
+impl<Input, Func> LetCancelled<Input, Func> {
+    pub fn new(input: Input, func: Func) -> Self {
+        Self { input, func }
+    }
+}
+
+impl<NextSender, Input, Func> Sender for LetCancelled<Input, Func>
+where
+    Input: Sender,
+    Func: 'static + Send + FnOnce() -> NextSender,
+    NextSender: Sender<Output = Input::Output>,
+    NextSender::Error: Into<Input::Error>,
+{
+    type Output = Input::Output;
+    type Error = Input::Error;
+    type Scheduler = Input::Scheduler;
+
+    #[inline]
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        self.input.start(LetCancelledReceiver {
+            guard: PanicGuard::new(&receiver.get_env()),
+            next: receiver,
+            func: self.func,
+        });
+    }
+
+    // Completions may come from the returned sender, same as for `and_then()`.
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        None
+    }
+}
+
+pub struct LetCancelledReceiver<Func, NextReceiver> {
+    next: NextReceiver,
+    func: Func,
+    guard: PanicGuard,
+}
+
+impl<Func, NextReceiver, Ret> Receiver for LetCancelledReceiver<Func, NextReceiver>
+where
+    Func: FnOnce() -> Ret,
+    Ret: Sender,
+    Ret::Error: Into<NextReceiver::Error>,
+    NextReceiver: 'static + Send + Receiver<Input = Ret::Output>,
+    NextReceiver::Error: 'static + Send,
+{
+    type Input = Ret::Output;
+    type Error = NextReceiver::Error;
+
+    #[inline]
+    fn set_value(self, value: Self::Input) {
+        self.next.set_value(value);
+    }
+
+    #[inline]
+    fn set_error(self, error: Self::Error) {
+        self.next.set_error(error);
+    }
+
+    #[inline]
+    fn set_cancelled(self) {
+        if let Some(sender) = self.guard.call(self.func) {
+            MapError::new(sender, Into::into).start(self.next);
+        }
+    }
+
+    #[inline]
+    fn get_stop_token(&self) -> StopToken {
+        self.next.get_stop_token()
+    }
+
+    #[inline]
+    fn get_env(&self) -> Env {
+        self.next.get_env()
+    }
+}
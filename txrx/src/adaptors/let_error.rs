@@ -0,0 +1,102 @@
+use crate::adaptors::map_error::MapError;
+use crate::env::Env;
+use crate::stop_token::StopToken;
+use crate::traits::{Channel, Receiver, Sender};
+use crate::utility::PanicGuard;
+use core::marker::PhantomData;
+
+/// Sender that starts a fallback sender when the input sender sends an error.
+/// See [`let_error()`](crate::SenderExt::let_error) for details.
+pub struct LetError<Input, Func> {
+    input: Input,
+    func: Func,
+}
+
+impl<Input, Func> LetError<Input, Func> {
+    pub fn new(input: Input, func: Func) -> Self {
+        Self { input, func }
+    }
+}
+
+impl<NextSender, Input, Func> Sender for LetError<Input, Func>
+where
+    Input: Sender,
+    Func: 'static + Send + FnOnce(Input::Error) -> NextSender,
+    NextSender: Sender<Output = Input::Output>,
+    NextSender::Error: Into<Input::Error>,
+{
+    type Output = Input::Output;
+    type Error = Input::Error;
+    type Scheduler = Input::Scheduler;
+
+    #[inline]
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        self.input.start(LetErrorReceiver::new(self.func, receiver));
+    }
+
+    // Completions may come from the returned sender, same as for `and_then()`.
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        None
+    }
+}
+
+pub struct LetErrorReceiver<Error, Func, NextReceiver> {
+    next: NextReceiver,
+    func: Func,
+    guard: PanicGuard,
+    _ph: PhantomData<Error>,
+}
+
+impl<Error, Func, NextReceiver: Receiver> LetErrorReceiver<Error, Func, NextReceiver> {
+    fn new(func: Func, next: NextReceiver) -> Self {
+        Self {
+            guard: PanicGuard::new(&next.get_env()),
+            next,
+            func,
+            _ph: PhantomData,
+        }
+    }
+}
+
+impl<Error, Func, NextReceiver, Ret> Receiver for LetErrorReceiver<Error, Func, NextReceiver>
+where
+    Func: FnOnce(Error) -> Ret,
+    Ret: Sender,
+    Ret::Error: Into<NextReceiver::Error>,
+    NextReceiver: 'static + Send + Receiver<Input = Ret::Output>,
+    NextReceiver::Error: 'static + Send,
+{
+    type Input = Ret::Output;
+    type Error = Error;
+
+    #[inline]
+    fn set_value(self, value: Self::Input) {
+        self.next.set_value(value);
+    }
+
+    #[inline]
+    fn set_error(self, error: Self::Error) {
+        let func = self.func;
+        if let Some(sender) = self.guard.call(move || func(error)) {
+            MapError::new(sender, Into::into).start(self.next);
+        }
+    }
+
+    #[inline]
+    fn set_cancelled(self) {
+        self.next.set_cancelled();
+    }
+
+    #[inline]
+    fn get_stop_token(&self) -> StopToken {
+        self.next.get_stop_token()
+    }
+
+    #[inline]
+    fn get_env(&self) -> Env {
+        self.next.get_env()
+    }
+}
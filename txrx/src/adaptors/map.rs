@@ -1,5 +1,8 @@
-use crate::traits::{Receiver as ReceiverT, Sender};
-use std::marker::PhantomData;
+use crate::env::Env;
+use crate::stop_token::StopToken;
+use crate::traits::{Channel, Connect, Receiver as ReceiverT, Sender};
+use crate::utility::PanicGuard;
+use core::marker::PhantomData;
 
 pub struct Map<S, F> {
     sender: S,
@@ -8,12 +11,14 @@ pub struct Map<S, F> {
 pub struct Receiver<Input, Recv, Func> {
     receiver: Recv,
     func: Func,
+    guard: PanicGuard,
     _phantom: PhantomData<Input>,
 }
 
-impl<Input, Recv, Func> Receiver<Input, Recv, Func> {
-    fn new(receiver: Recv, func: Func) -> Self {
+impl<Input, Recv: ReceiverT, Func> Receiver<Input, Recv, Func> {
+    pub(crate) fn new(receiver: Recv, func: Func) -> Self {
         Self {
+            guard: PanicGuard::new(&receiver.get_env()),
             receiver,
             func,
             _phantom: PhantomData,
@@ -34,18 +39,34 @@ where
     Ret: 'static + Send,
 {
     type Output = Ret;
+    type Error = Src::Error;
     type Scheduler = Src::Scheduler;
 
     #[inline]
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + ReceiverT<Input = Self::Output>,
+        R: 'static + Send + ReceiverT<Input = Self::Output, Error = Self::Error>,
     {
         self.sender.start(Receiver::new(receiver, self.func));
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        self.sender.get_scheduler()
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        self.sender.get_completion_scheduler(channel)
+    }
+}
+
+// The operation state of the input sender is used as is, `func` is stored in its receiver.
+impl<Src, Func, Ret, R> Connect<R> for Map<Src, Func>
+where
+    Src: Sender + Connect<Receiver<Src::Output, R, Func>>,
+    Func: FnOnce(Src::Output) -> Ret,
+    R: ReceiverT<Input = Ret, Error = Src::Error>,
+{
+    type Operation = <Src as Connect<Receiver<Src::Output, R, Func>>>::Operation;
+
+    #[inline]
+    fn connect(self, receiver: R) -> Self::Operation {
+        self.sender.connect(Receiver::new(receiver, self.func))
     }
 }
 
@@ -53,17 +74,20 @@ impl<I, Recv, Func, Ret> ReceiverT for Receiver<I, Recv, Func>
 where
     Func: FnOnce(I) -> Ret,
     Recv: ReceiverT<Input = Ret>,
-    I: 'static + Send,
 {
     type Input = I;
+    type Error = Recv::Error;
 
     #[inline]
     fn set_value(self, value: Self::Input) {
-        self.receiver.set_value((self.func)(value));
+        let func = self.func;
+        if let Some(result) = self.guard.call(move || func(value)) {
+            self.receiver.set_value(result);
+        }
     }
 
     #[inline]
-    fn set_error(self, error: crate::Error) {
+    fn set_error(self, error: Self::Error) {
         self.receiver.set_error(error);
     }
 
@@ -71,4 +95,35 @@ where
     fn set_cancelled(self) {
         self.receiver.set_cancelled();
     }
+
+    #[inline]
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+
+    #[inline]
+    fn get_env(&self) -> Env {
+        self.receiver.get_env()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manual_executor::ManualExecutor;
+    use crate::test::Stoppable;
+    use crate::traits::Scheduler;
+    use crate::SenderExt;
+
+    #[test]
+    fn forwards_stop_requests() {
+        let executor = ManualExecutor::new();
+        let sender = executor
+            .scheduler()
+            .schedule()
+            .map(|_| -> i32 { panic!("Never called") });
+        let started = Stoppable::start(sender);
+        started.request_stop();
+        assert!(executor.runner().run_one());
+        assert!(started.take().unwrap().is_cancelled());
+    }
 }
@@ -0,0 +1,96 @@
+use crate::env::Env;
+use crate::stop_token::StopToken;
+use crate::traits::{Channel, Receiver as ReceiverT, Sender};
+use crate::utility::PanicGuard;
+use core::marker::PhantomData;
+
+/// Sender that transforms the error sent by the input sender.
+/// See [`map_error()`](crate::SenderExt::map_error) for details.
+pub struct MapError<S, F> {
+    sender: S,
+    func: F,
+}
+
+impl<S, F> MapError<S, F> {
+    pub fn new(sender: S, func: F) -> Self {
+        Self { sender, func }
+    }
+}
+
+pub struct Receiver<Error, Recv, Func> {
+    receiver: Recv,
+    func: Func,
+    guard: PanicGuard,
+    _phantom: PhantomData<Error>,
+}
+
+impl<Error, Recv: ReceiverT, Func> Receiver<Error, Recv, Func> {
+    pub(crate) fn new(receiver: Recv, func: Func) -> Self {
+        Self {
+            guard: PanicGuard::new(&receiver.get_env()),
+            receiver,
+            func,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<Src, Func, Ret> Sender for MapError<Src, Func>
+where
+    Src: Sender,
+    Func: 'static + Send + FnOnce(Src::Error) -> Ret,
+    Ret: 'static + Send,
+{
+    type Output = Src::Output;
+    type Error = Ret;
+    type Scheduler = Src::Scheduler;
+
+    #[inline]
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + ReceiverT<Input = Self::Output, Error = Self::Error>,
+    {
+        self.sender.start(Receiver::new(receiver, self.func));
+    }
+
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        self.sender.get_completion_scheduler(channel)
+    }
+}
+
+impl<E, Recv, Func> ReceiverT for Receiver<E, Recv, Func>
+where
+    Func: FnOnce(E) -> Recv::Error,
+    Recv: ReceiverT,
+{
+    type Input = Recv::Input;
+    type Error = E;
+
+    #[inline]
+    fn set_value(self, value: Self::Input) {
+        self.receiver.set_value(value);
+    }
+
+    #[inline]
+    fn set_error(self, error: Self::Error) {
+        let func = self.func;
+        if let Some(error) = self.guard.call(move || func(error)) {
+            self.receiver.set_error(error);
+        }
+    }
+
+    #[inline]
+    fn set_cancelled(self) {
+        self.receiver.set_cancelled();
+    }
+
+    #[inline]
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+
+    #[inline]
+    fn get_env(&self) -> Env {
+        self.receiver.get_env()
+    }
+}
@@ -1,6 +1,19 @@
 pub mod and_then;
 pub mod bulk;
+#[cfg(feature = "std")]
+pub mod catch_unwind;
 pub mod ensure_started;
+pub mod let_cancelled;
+pub mod let_error;
 pub mod map;
+pub mod map_error;
+pub mod split;
+#[cfg(feature = "std")]
+pub mod timeout;
 pub mod transfer;
+pub mod upon_cancelled;
+pub mod upon_error;
+pub mod when_all;
+pub mod when_any;
 pub mod when_both;
+pub mod with_env;
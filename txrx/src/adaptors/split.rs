@@ -0,0 +1,211 @@
+use crate::priv_sync::Mutex;
+use crate::traits::receiver::DynReceiver;
+use crate::traits::{Receiver, Sender};
+use crate::utility::ReceiverRef;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+type BoxedReceiver<T, E> = Box<dyn Send + DynReceiver<Input = T, Error = Arc<E>>>;
+
+struct Data<S: Sender> {
+    sender: Option<S>,
+    result: Option<crate::Result<S::Output, Arc<S::Error>>>,
+    receivers: Vec<BoxedReceiver<S::Output, S::Error>>,
+}
+
+struct SharedState<S: Sender> {
+    data: Mutex<Data<S>>,
+}
+
+impl<S: Sender> SharedState<S>
+where
+    S::Output: Clone,
+{
+    fn set_result(&self, result: crate::Result<S::Output, Arc<S::Error>>) {
+        let receivers = {
+            let mut data = self.data.lock();
+            data.result = Some(result.clone());
+            core::mem::take(&mut data.receivers)
+        };
+        for receiver in receivers {
+            complete(receiver, result.clone());
+        }
+    }
+}
+
+fn complete<T, E, R>(receiver: R, result: crate::Result<T, E>)
+where
+    R: Receiver<Input = T, Error = E>,
+{
+    match result {
+        Ok(Some(value)) => receiver.set_value(value),
+        Ok(None) => receiver.set_cancelled(),
+        Err(error) => receiver.set_error(error),
+    }
+}
+
+/// A cloneable sender that sends the result of the input sender to every receiver it is started
+/// with. See [`split()`](crate::SenderExt::split) for details.
+pub struct Split<S: Sender> {
+    state: Arc<SharedState<S>>,
+}
+
+impl<S: Sender> Split<S> {
+    pub fn new(sender: S) -> Self {
+        Self {
+            state: Arc::new(SharedState {
+                data: Mutex::new(Data {
+                    sender: Some(sender),
+                    result: None,
+                    receivers: Vec::new(),
+                }),
+            }),
+        }
+    }
+}
+
+impl<S: Sender> Clone for Split<S> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<S> Sender for Split<S>
+where
+    S: 'static + Send + Sender,
+    S::Output: Clone,
+    S::Error: Sync,
+{
+    type Output = S::Output;
+    type Error = Arc<S::Error>;
+    type Scheduler = S::Scheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        let mut data = self.state.data.lock();
+        if let Some(result) = data.result.clone() {
+            drop(data);
+            complete(receiver, result);
+            return;
+        }
+
+        data.receivers.push(Box::new(ReceiverRef::new(receiver)));
+        let sender = data.sender.take();
+        drop(data);
+        if let Some(sender) = sender {
+            sender.start(SplitReceiver {
+                state: self.state,
+                completed: false,
+            });
+        }
+    }
+}
+
+struct SplitReceiver<S: Sender>
+where
+    S::Output: Clone,
+{
+    state: Arc<SharedState<S>>,
+    completed: bool,
+}
+
+impl<S: Sender> SplitReceiver<S>
+where
+    S::Output: Clone,
+{
+    fn complete(mut self, result: crate::Result<S::Output, Arc<S::Error>>) {
+        self.completed = true;
+        self.state.set_result(result);
+    }
+}
+
+// The clones of the split sender keep the attached receivers alive, so they would wait forever if
+// the input sender dropped this receiver without completing it. Cancel them instead.
+impl<S: Sender> Drop for SplitReceiver<S>
+where
+    S::Output: Clone,
+{
+    fn drop(&mut self) {
+        if !self.completed {
+            self.state.set_result(Ok(None));
+        }
+    }
+}
+
+impl<S: Sender> Receiver for SplitReceiver<S>
+where
+    S::Output: Clone,
+{
+    type Input = S::Output;
+    type Error = S::Error;
+
+    fn set_value(self, value: Self::Input) {
+        self.complete(Ok(Some(value)));
+    }
+
+    fn set_error(self, error: Self::Error) {
+        self.complete(Err(Arc::new(error)));
+    }
+
+    fn set_cancelled(self) {
+        self.complete(Ok(None));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test::ManualSender;
+    use crate::SenderExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn fan_out() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let (manual, trigger) = ManualSender::new();
+        let split = manual
+            .map({
+                let count = count.clone();
+                move |_| {
+                    count.fetch_add(1, Ordering::Relaxed);
+                    String::from("hello")
+                }
+            })
+            .split();
+        assert_eq!(count.load(Ordering::Relaxed), 0);
+
+        let first = split.clone().map(|x| x.len()).ensure_started();
+        let second = split.clone().ensure_started();
+        trigger.trigger();
+        assert_eq!(first.sync_wait().unwrap(), 5);
+        assert_eq!(second.sync_wait().unwrap(), "hello");
+
+        // Attaching after completion sends the stored result.
+        assert_eq!(split.sync_wait().unwrap(), "hello");
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn shared_error() {
+        let split = crate::factories::error("failed").map(|_: ()| 1).split();
+        let first = split.clone().sync_wait().unwrap_error();
+        let second = split.sync_wait().unwrap_error();
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn input_drops_receiver() {
+        let (manual, trigger) = ManualSender::new();
+        let split = manual.split();
+        let first = split.clone().ensure_started();
+        // Dropping the trigger drops the receiver the input sender was started with.
+        drop(trigger);
+        assert!(first.sync_wait().is_cancelled());
+        assert!(split.sync_wait().is_cancelled());
+    }
+}
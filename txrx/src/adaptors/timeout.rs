@@ -0,0 +1,245 @@
+use crate::env::Env;
+use crate::priv_sync::Mutex;
+use crate::stop_token::{StopCallback, StopSource, StopToken};
+use crate::traits::{Receiver, Sender, TimeScheduler};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Error sent by [`timeout()`](crate::SenderExt::timeout) when the input sender doesn't complete
+/// in time.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TimedOut;
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("operation timed out")
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Decides how a [`Timeout`] completes its receiver when the deadline is reached.
+pub trait TimeoutPolicy<E> {
+    fn timed_out<R: Receiver<Error = E>>(receiver: R);
+}
+
+/// Completes the receiver with a [`TimedOut`] error, the default policy.
+pub struct ErrorOnTimeout;
+
+/// Cancels the receiver, see [`Timeout::cancel_on_timeout()`].
+pub struct CancelOnTimeout;
+
+impl<E: From<TimedOut>> TimeoutPolicy<E> for ErrorOnTimeout {
+    fn timed_out<R: Receiver<Error = E>>(receiver: R) {
+        receiver.set_error(TimedOut.into());
+    }
+}
+
+impl<E> TimeoutPolicy<E> for CancelOnTimeout {
+    fn timed_out<R: Receiver<Error = E>>(receiver: R) {
+        receiver.set_cancelled();
+    }
+}
+
+/// Sender that gives up on the input sender after a timeout.
+/// See [`timeout()`](crate::SenderExt::timeout) for details.
+pub struct Timeout<S, Sched, Policy = ErrorOnTimeout> {
+    sender: S,
+    scheduler: Sched,
+    duration: Duration,
+    _policy: PhantomData<fn(Policy)>,
+}
+
+impl<S, Sched> Timeout<S, Sched> {
+    pub fn new(sender: S, scheduler: Sched, duration: Duration) -> Self {
+        Self {
+            sender,
+            scheduler,
+            duration,
+            _policy: PhantomData,
+        }
+    }
+
+    /// Cancels the receiver instead of sending [`TimedOut`] when the timeout expires.
+    pub fn cancel_on_timeout(self) -> Timeout<S, Sched, CancelOnTimeout> {
+        Timeout {
+            sender: self.sender,
+            scheduler: self.scheduler,
+            duration: self.duration,
+            _policy: PhantomData,
+        }
+    }
+}
+
+impl<S, Sched, Policy> Sender for Timeout<S, Sched, Policy>
+where
+    S: Sender,
+    Sched: 'static + Send + Clone + TimeScheduler,
+    Policy: 'static + TimeoutPolicy<S::Error>,
+{
+    type Output = S::Output;
+    type Error = S::Error;
+    type Scheduler = S::Scheduler;
+
+    fn start<R>(mut self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        let state = SharedState::new(receiver);
+        self.scheduler
+            .schedule_after(self.duration)
+            .start(TimerReceiver {
+                state: state.clone(),
+                _phantom: PhantomData::<fn(Policy, Sched)>,
+            });
+        self.sender.start(InputReceiver { state });
+    }
+}
+
+struct Data<Next> {
+    next: Option<Next>,
+    // Forwards stop requests from `next` to both the input sender and the timer.
+    stop_callback: Option<StopCallback>,
+}
+
+struct SharedState<Next> {
+    data: Mutex<Data<Next>>,
+    stop_source: StopSource,
+    env: Env,
+}
+
+impl<Next: Receiver> SharedState<Next> {
+    fn new(next: Next) -> Arc<Self> {
+        let stop_source = StopSource::new();
+        let stop_callback = next.get_stop_token().on_stop({
+            let stop_source = stop_source.clone();
+            move || {
+                stop_source.request_stop();
+            }
+        });
+        let env = next.get_env();
+        Arc::new(Self {
+            data: Mutex::new(Data {
+                next: Some(next),
+                stop_callback: Some(stop_callback),
+            }),
+            stop_source,
+            env,
+        })
+    }
+
+    // Returns `next` if the caller decided the result. The loser, the timer or the input sender,
+    // is asked to stop.
+    fn take_next(&self) -> Option<Next> {
+        let (next, stop_callback) = {
+            let mut data = self.data.lock();
+            (data.next.take(), data.stop_callback.take())
+        };
+        drop(stop_callback);
+        if next.is_some() {
+            self.stop_source.request_stop();
+        }
+        next
+    }
+}
+
+struct InputReceiver<Next> {
+    state: Arc<SharedState<Next>>,
+}
+
+impl<Next: Receiver> Receiver for InputReceiver<Next> {
+    type Input = Next::Input;
+    type Error = Next::Error;
+
+    fn set_value(self, value: Self::Input) {
+        if let Some(next) = self.state.take_next() {
+            next.set_value(value);
+        }
+    }
+
+    fn set_error(self, error: Self::Error) {
+        if let Some(next) = self.state.take_next() {
+            next.set_error(error);
+        }
+    }
+
+    fn set_cancelled(self) {
+        if let Some(next) = self.state.take_next() {
+            next.set_cancelled();
+        }
+    }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+
+    fn get_env(&self) -> Env {
+        self.state.env.clone()
+    }
+}
+
+struct TimerReceiver<Next, Policy, Sched> {
+    state: Arc<SharedState<Next>>,
+    _phantom: PhantomData<fn(Policy, Sched)>,
+}
+
+impl<Next, Policy, Sched> Receiver for TimerReceiver<Next, Policy, Sched>
+where
+    Next: Receiver,
+    Policy: TimeoutPolicy<Next::Error>,
+    Sched: TimeScheduler,
+{
+    type Input = ();
+    type Error = <Sched::TimedSender as Sender>::Error;
+
+    fn set_value(self, _value: Self::Input) {
+        if let Some(next) = self.state.take_next() {
+            Policy::timed_out(next);
+        }
+    }
+
+    // The timer only stops early if the input sender completed first, or if the receiver asked
+    // for stop, which the input sender handles.
+    fn set_error(self, _error: Self::Error) {}
+
+    fn set_cancelled(self) {}
+
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimedOut;
+    use crate::test::ManualSender;
+    use crate::SenderExt;
+    use std::time::Duration;
+
+    #[test]
+    fn times_out() {
+        let (manual, _trigger) = ManualSender::new();
+        let error = manual
+            .timeout(Duration::from_millis(5))
+            .sync_wait()
+            .unwrap_error();
+        assert!(error.downcast_ref::<TimedOut>().is_some());
+
+        let (manual, _trigger) = ManualSender::new();
+        let result = manual
+            .timeout(Duration::from_millis(5))
+            .cancel_on_timeout()
+            .sync_wait();
+        assert!(result.is_cancelled());
+    }
+
+    #[test]
+    fn completes_in_time() {
+        let value = crate::just(10)
+            .timeout(Duration::from_secs(60))
+            .sync_wait()
+            .unwrap();
+        assert_eq!(value, 10);
+    }
+}
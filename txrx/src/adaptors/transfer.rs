@@ -1,4 +1,6 @@
-use crate::traits::{Receiver, Scheduler, Sender, Work};
+use crate::env::Env;
+use crate::stop_token::StopToken;
+use crate::traits::{Channel, Receiver, Scheduler, Sender, Work};
 
 pub struct Transfer<SenderT, SchedulerT> {
     input: SenderT,
@@ -17,11 +19,12 @@ where
     Sc: 'static + Send + Clone + Scheduler,
 {
     type Output = S::Output;
+    type Error = S::Error;
     type Scheduler = Sc;
 
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         self.input.start(TransferReceiver {
             next: receiver,
@@ -29,32 +32,33 @@ where
         });
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        self.scheduler.clone()
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(self.scheduler.clone())
     }
 }
 
+/// Completes a receiver when run by a scheduler, or cancels it if the scheduler drops the job.
 pub struct TransferJob<Next: Receiver> {
     next: Next,
-    data: crate::Result<Next::Input>,
+    data: crate::Result<Next::Input, Next::Error>,
 }
 
 impl<Next: Receiver> TransferJob<Next> {
-    fn value(next: Next, value: Next::Input) -> Self {
+    pub(crate) fn value(next: Next, value: Next::Input) -> Self {
         Self {
             next,
             data: Ok(Some(value)),
         }
     }
 
-    fn error(next: Next, err: crate::Error) -> Self {
+    pub(crate) fn error(next: Next, err: Next::Error) -> Self {
         Self {
             next,
             data: Err(err),
         }
     }
 
-    fn done(next: Next) -> Self {
+    pub(crate) fn done(next: Next) -> Self {
         Self {
             next,
             data: Ok(None),
@@ -66,12 +70,18 @@ impl<Next: Receiver> Work for TransferJob<Next> {
     fn execute(self) {
         match self.data {
             Ok(value) => match value {
+                // Stop may have been requested while the job was queued.
+                Some(_) if self.next.get_stop_token().stop_requested() => self.next.set_cancelled(),
                 Some(v) => self.next.set_value(v),
                 None => self.next.set_cancelled(),
             },
             Err(err) => self.next.set_error(err),
         }
     }
+
+    fn cancel(self) {
+        self.next.set_cancelled();
+    }
 }
 
 pub struct TransferReceiver<Next, SchedT> {
@@ -84,18 +94,58 @@ where
     Next: 'static + Send + Receiver,
     SchedT: 'static + Send + Scheduler,
     Next::Input: 'static + Send,
+    Next::Error: 'static + Send,
 {
     type Input = Next::Input;
+    type Error = Next::Error;
 
     fn set_value(mut self, value: Self::Input) {
         self.scheduler.execute(TransferJob::value(self.next, value));
     }
 
-    fn set_error(mut self, error: crate::Error) {
+    fn set_error(mut self, error: Self::Error) {
         self.scheduler.execute(TransferJob::error(self.next, error));
     }
 
     fn set_cancelled(mut self) {
         self.scheduler.execute(TransferJob::done(self.next));
     }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.next.get_stop_token()
+    }
+
+    fn get_env(&self) -> Env {
+        self.next.get_env()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manual_executor::ManualExecutor;
+    use crate::test::{ManualSender, Stoppable};
+    use crate::SenderExt;
+
+    #[test]
+    fn stop_cancels_queued_job() {
+        let executor = ManualExecutor::new();
+        let (sender, trigger) = ManualSender::new();
+        let started = Stoppable::start(sender.transfer(executor.scheduler()));
+        trigger.trigger();
+        // The value is queued on the executor when stop is requested.
+        started.request_stop();
+        assert!(executor.runner().run_one());
+        assert!(started.take().unwrap().is_cancelled());
+    }
+
+    #[test]
+    fn shutdown_cancels_queued_job() {
+        let executor = ManualExecutor::new();
+        let (sender, trigger) = ManualSender::new();
+        let fut = sender.transfer(executor.scheduler()).ensure_started();
+        trigger.trigger();
+        assert!(!fut.is_complete());
+        executor.shutdown();
+        assert!(fut.sync_wait().is_cancelled());
+    }
 }
@@ -0,0 +1,89 @@
+use crate::env::Env;
+use crate::stop_token::StopToken;
+use crate::traits::{Channel, Receiver as ReceiverT, Sender};
+use crate::utility::PanicGuard;
+
+/// Sender that recovers from the input sender being cancelled.
+/// See [`upon_cancelled()`](crate::SenderExt::upon_cancelled) for details.
+pub struct UponCancelled<S, F> {
+    sender: S,
+    func: F,
+}
+
+impl<S, F> UponCancelled<S, F> {
+    pub fn new(sender: S, func: F) -> Self {
+        Self { sender, func }
+    }
+}
+
+pub struct Receiver<Recv, Func> {
+    receiver: Recv,
+    func: Func,
+    guard: PanicGuard,
+}
+
+impl<Src, Func> Sender for UponCancelled<Src, Func>
+where
+    Src: Sender,
+    Func: 'static + Send + FnOnce() -> Src::Output,
+{
+    type Output = Src::Output;
+    type Error = Src::Error;
+    type Scheduler = Src::Scheduler;
+
+    #[inline]
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + ReceiverT<Input = Self::Output, Error = Self::Error>,
+    {
+        self.sender.start(Receiver {
+            guard: PanicGuard::new(&receiver.get_env()),
+            receiver,
+            func: self.func,
+        });
+    }
+
+    // Values are sent from both the value and the cancelled channel of the input sender.
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        match channel {
+            Channel::Error => self.sender.get_completion_scheduler(channel),
+            Channel::Value | Channel::Cancelled => None,
+        }
+    }
+}
+
+impl<Recv, Func> ReceiverT for Receiver<Recv, Func>
+where
+    Func: FnOnce() -> Recv::Input,
+    Recv: ReceiverT,
+{
+    type Input = Recv::Input;
+    type Error = Recv::Error;
+
+    #[inline]
+    fn set_value(self, value: Self::Input) {
+        self.receiver.set_value(value);
+    }
+
+    #[inline]
+    fn set_error(self, error: Self::Error) {
+        self.receiver.set_error(error);
+    }
+
+    #[inline]
+    fn set_cancelled(self) {
+        if let Some(value) = self.guard.call(self.func) {
+            self.receiver.set_value(value);
+        }
+    }
+
+    #[inline]
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+
+    #[inline]
+    fn get_env(&self) -> Env {
+        self.receiver.get_env()
+    }
+}
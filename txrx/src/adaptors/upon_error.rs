@@ -0,0 +1,99 @@
+use crate::env::Env;
+use crate::stop_token::StopToken;
+use crate::traits::{Channel, Receiver as ReceiverT, Sender};
+use crate::utility::PanicGuard;
+use core::marker::PhantomData;
+
+/// Sender that recovers from an error sent by the input sender.
+/// See [`upon_error()`](crate::SenderExt::upon_error) for details.
+pub struct UponError<S, F> {
+    sender: S,
+    func: F,
+}
+
+impl<S, F> UponError<S, F> {
+    pub fn new(sender: S, func: F) -> Self {
+        Self { sender, func }
+    }
+}
+
+pub struct Receiver<Error, Recv, Func> {
+    receiver: Recv,
+    func: Func,
+    guard: PanicGuard,
+    _phantom: PhantomData<Error>,
+}
+
+impl<Error, Recv: ReceiverT, Func> Receiver<Error, Recv, Func> {
+    fn new(receiver: Recv, func: Func) -> Self {
+        Self {
+            guard: PanicGuard::new(&receiver.get_env()),
+            receiver,
+            func,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<Src, Func> Sender for UponError<Src, Func>
+where
+    Src: Sender,
+    Func: 'static + Send + FnOnce(Src::Error) -> Src::Output,
+{
+    type Output = Src::Output;
+    type Error = Src::Error;
+    type Scheduler = Src::Scheduler;
+
+    #[inline]
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + ReceiverT<Input = Self::Output, Error = Self::Error>,
+    {
+        self.sender.start(Receiver::new(receiver, self.func));
+    }
+
+    // Values are sent from both the value and the error channel of the input sender.
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        match channel {
+            Channel::Cancelled => self.sender.get_completion_scheduler(channel),
+            Channel::Value | Channel::Error => None,
+        }
+    }
+}
+
+impl<E, Recv, Func> ReceiverT for Receiver<E, Recv, Func>
+where
+    Func: FnOnce(E) -> Recv::Input,
+    Recv: ReceiverT,
+{
+    type Input = Recv::Input;
+    type Error = E;
+
+    #[inline]
+    fn set_value(self, value: Self::Input) {
+        self.receiver.set_value(value);
+    }
+
+    #[inline]
+    fn set_error(self, error: Self::Error) {
+        let func = self.func;
+        if let Some(value) = self.guard.call(move || func(error)) {
+            self.receiver.set_value(value);
+        }
+    }
+
+    #[inline]
+    fn set_cancelled(self) {
+        self.receiver.set_cancelled();
+    }
+
+    #[inline]
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+
+    #[inline]
+    fn get_env(&self) -> Env {
+        self.receiver.get_env()
+    }
+}
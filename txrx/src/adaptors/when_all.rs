@@ -0,0 +1,363 @@
+//! Senders that complete when all of a group of senders complete.
+//!
+//! See [`when_all()`] and [`when_all_iter()`].
+use crate::env::Env;
+use crate::priv_sync::Mutex;
+use crate::stop_token::{StopCallback, StopSource, StopToken};
+use crate::traits::{Receiver, Sender};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// Returns a sender that completes when all senders in the tuple `senders` complete.
+///
+/// The output is a flat tuple with the values of all senders, in order. The error type is the
+/// error type of the first sender, errors from the other senders are converted using `Into`.
+///
+/// If any sender sends an error or is cancelled, the receiver is completed immediately and stop is
+/// requested on the senders that are still running. Tuples with up to 8 senders are supported.
+///
+/// The receiver is completed on whatever context the deciding sender completes on, so the
+/// completion scheduler is unknown for all channels.
+///
+/// ## Examples
+///
+/// ```
+/// use txrx::SenderExt;
+///
+/// let (a, b, c) = txrx::when_all((txrx::just(1), txrx::just("two"), txrx::just(3.0)))
+///     .sync_wait()
+///     .unwrap();
+/// assert_eq!((a, b, c), (1, "two", 3.0));
+/// ```
+pub fn when_all<T>(senders: T) -> WhenAll<T> {
+    WhenAll { senders }
+}
+
+/// Returns a sender that completes when all senders in `senders` complete.
+///
+/// The output is a `Vec` with the values of all senders, in iteration order. Errors and
+/// cancellation are handled the same way as for [`when_all()`].
+///
+/// ## Examples
+///
+/// ```
+/// use txrx::SenderExt;
+///
+/// let values = txrx::when_all_iter((0..4).map(|x| txrx::just(x).map(|x| x * 2)))
+///     .sync_wait()
+///     .unwrap();
+/// assert_eq!(values, vec![0, 2, 4, 6]);
+/// ```
+pub fn when_all_iter<I>(senders: I) -> WhenAllIter<I::Item>
+where
+    I: IntoIterator,
+    I::Item: Sender,
+{
+    WhenAllIter {
+        senders: senders.into_iter().collect(),
+    }
+}
+
+/// Sender returned by [`when_all()`].
+pub struct WhenAll<T> {
+    senders: T,
+}
+
+/// Sender returned by [`when_all_iter()`].
+pub struct WhenAllIter<S> {
+    senders: Vec<S>,
+}
+
+/// Storage for the values of the senders that have completed so far.
+trait Slots {
+    type Output;
+
+    fn take_output(&mut self) -> Self::Output;
+}
+
+impl<T> Slots for Vec<Option<T>> {
+    type Output = Vec<T>;
+
+    fn take_output(&mut self) -> Self::Output {
+        self.drain(..).map(|x| x.unwrap()).collect()
+    }
+}
+
+struct Data<S, Next> {
+    slots: S,
+    remaining: usize,
+    next: Option<Next>,
+    // Forwards stop requests from `next` to all senders, dropped once `next` is completed.
+    stop_callback: Option<StopCallback>,
+}
+
+struct SharedState<S, Next> {
+    data: Mutex<Data<S, Next>>,
+    stop_source: StopSource,
+    env: Env,
+}
+
+impl<S: Slots, Next: Receiver<Input = S::Output>> SharedState<S, Next> {
+    fn new(slots: S, remaining: usize, next: Next) -> Arc<Self> {
+        let stop_source = StopSource::new();
+        let stop_callback = next.get_stop_token().on_stop({
+            let stop_source = stop_source.clone();
+            move || {
+                stop_source.request_stop();
+            }
+        });
+        let env = next.get_env();
+        Arc::new(Self {
+            data: Mutex::new(Data {
+                slots,
+                remaining,
+                next: Some(next),
+                stop_callback: Some(stop_callback),
+            }),
+            stop_source,
+            env,
+        })
+    }
+
+    fn take_next(&self) -> Option<Next> {
+        let (next, stop_callback) = {
+            let mut data = self.data.lock();
+            (data.next.take(), data.stop_callback.take())
+        };
+        drop(stop_callback);
+        next
+    }
+
+    fn set_value<T>(&self, store: fn(&mut S, usize, T), index: usize, value: T) {
+        let completed = {
+            let mut data = self.data.lock();
+            if data.next.is_none() {
+                return;
+            }
+            store(&mut data.slots, index, value);
+            data.remaining -= 1;
+            if data.remaining == 0 {
+                let output = data.slots.take_output();
+                Some((output, data.next.take(), data.stop_callback.take()))
+            } else {
+                None
+            }
+        };
+        if let Some((output, Some(next), stop_callback)) = completed {
+            drop(stop_callback);
+            next.set_value(output);
+        }
+    }
+
+    fn set_error(&self, error: Next::Error) {
+        if let Some(next) = self.take_next() {
+            // The result is decided, the remaining senders can stop.
+            self.stop_source.request_stop();
+            next.set_error(error);
+        }
+    }
+
+    fn set_cancelled(&self) {
+        if let Some(next) = self.take_next() {
+            self.stop_source.request_stop();
+            next.set_cancelled();
+        }
+    }
+}
+
+struct ElementReceiver<S, Next, T, E> {
+    state: Arc<SharedState<S, Next>>,
+    store: fn(&mut S, usize, T),
+    index: usize,
+    _phantom: PhantomData<fn(E)>,
+}
+
+impl<S, Next, T, E> ElementReceiver<S, Next, T, E> {
+    fn new(state: Arc<SharedState<S, Next>>, store: fn(&mut S, usize, T), index: usize) -> Self {
+        Self {
+            state,
+            store,
+            index,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<S, Next, T, E> Receiver for ElementReceiver<S, Next, T, E>
+where
+    S: Slots,
+    Next: Receiver<Input = S::Output>,
+    E: Into<Next::Error>,
+{
+    type Input = T;
+    type Error = E;
+
+    fn set_value(self, value: Self::Input) {
+        self.state.set_value(self.store, self.index, value);
+    }
+
+    fn set_error(self, error: Self::Error) {
+        self.state.set_error(error.into());
+    }
+
+    fn set_cancelled(self) {
+        self.state.set_cancelled();
+    }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+
+    fn get_env(&self) -> Env {
+        self.state.env.clone()
+    }
+}
+
+impl<S: Sender> Sender for WhenAllIter<S> {
+    type Output = Vec<S::Output>;
+    type Error = S::Error;
+    type Scheduler = S::Scheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        if self.senders.is_empty() {
+            receiver.set_value(Vec::new());
+            return;
+        }
+
+        let slots: Vec<Option<S::Output>> = self.senders.iter().map(|_| None).collect();
+        let state = SharedState::new(slots, self.senders.len(), receiver);
+        for (index, sender) in self.senders.into_iter().enumerate() {
+            sender.start(ElementReceiver::<_, _, _, S::Error>::new(
+                state.clone(),
+                |slots: &mut Vec<Option<S::Output>>, index, value| slots[index] = Some(value),
+                index,
+            ));
+        }
+    }
+}
+
+macro_rules! when_all_tuple {
+    ($First:ident $first_idx:tt $(, $T:ident $idx:tt)*) => {
+        impl<$First, $($T),*> Slots for (Option<$First>, $(Option<$T>,)*) {
+            type Output = ($First, $($T,)*);
+
+            fn take_output(&mut self) -> Self::Output {
+                (self.$first_idx.take().unwrap(), $(self.$idx.take().unwrap(),)*)
+            }
+        }
+
+        impl<$First, $($T),*> Sender for WhenAll<($First, $($T,)*)>
+        where
+            $First: Sender,
+            $(
+                $T: Sender,
+                $T::Error: Into<$First::Error>,
+            )*
+        {
+            type Output = ($First::Output, $($T::Output,)*);
+            type Error = $First::Error;
+            type Scheduler = $First::Scheduler;
+
+            fn start<R>(self, receiver: R)
+            where
+                R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+            {
+                let count = 1 $(+ { stringify!($T); 1 })*;
+                let slots: (Option<$First::Output>, $(Option<$T::Output>,)*) = Default::default();
+                let state = SharedState::new(slots, count, receiver);
+
+                self.senders.$first_idx.start(
+                    ElementReceiver::<_, _, _, $First::Error>::new(
+                        state.clone(),
+                        |slots, _, value| slots.$first_idx = Some(value),
+                        $first_idx,
+                    ),
+                );
+                $(
+                    self.senders.$idx.start(ElementReceiver::<_, _, _, $T::Error>::new(
+                        state.clone(),
+                        |slots, _, value| slots.$idx = Some(value),
+                        $idx,
+                    ));
+                )*
+            }
+        }
+    };
+}
+
+when_all_tuple!(A 0);
+when_all_tuple!(A 0, B 1);
+when_all_tuple!(A 0, B 1, C 2);
+when_all_tuple!(A 0, B 1, C 2, D 3);
+when_all_tuple!(A 0, B 1, C 2, D 3, E 4);
+when_all_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+when_all_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+when_all_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+#[cfg(test)]
+mod tests {
+    use super::{when_all, when_all_iter};
+    use crate::manual_executor::ManualExecutor;
+    use crate::test::ManualSender;
+    use crate::traits::Scheduler;
+    use crate::SenderExt;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn flat_tuple() {
+        let result = when_all((
+            crate::just(1),
+            crate::just(2u8),
+            crate::just("3"),
+            crate::just(4.0),
+            crate::just('5'),
+        ))
+        .sync_wait()
+        .unwrap();
+        assert_eq!(result, (1, 2u8, "3", 4.0, '5'));
+    }
+
+    #[test]
+    fn first_error_stops_siblings() {
+        let executor = ManualExecutor::new();
+        let runner = executor.runner();
+        let (manual, trigger) = ManualSender::new();
+        let ran = Arc::new(AtomicBool::new(false));
+        let sender = when_all((
+            executor.scheduler().schedule().map({
+                let ran = ran.clone();
+                move |_| ran.store(true, Ordering::Relaxed)
+            }),
+            crate::factories::error("failed").map(|_: ()| ()),
+            manual,
+        ));
+
+        // Completes without waiting for the scheduled work or the manual sender.
+        let result = sender.sync_wait();
+        assert_eq!(result.unwrap_error().to_string(), "failed");
+        trigger.trigger();
+        // The scheduled work sees the stop request and cancels instead of sending its value.
+        assert!(runner.run_one());
+        assert!(!ran.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn iter() {
+        let result = when_all_iter(Vec::<crate::factories::just_sender::Just<i32>>::new())
+            .sync_wait()
+            .unwrap();
+        assert!(result.is_empty());
+
+        let result = when_all_iter(vec![
+            crate::just(1).boxed(),
+            crate::factories::cancelled().map(|_| 2).boxed(),
+        ])
+        .sync_wait();
+        assert!(result.is_cancelled());
+    }
+}
@@ -0,0 +1,286 @@
+//! Senders that complete with the first of a group of senders to complete.
+//!
+//! See [`when_any()`](crate::SenderExt::when_any) and [`when_any_iter()`].
+use crate::env::Env;
+use crate::priv_sync::Mutex;
+use crate::stop_token::{StopCallback, StopSource, StopToken};
+use crate::traits::{Receiver, Sender};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use either::Either;
+
+/// Returns a sender that completes with the first sender in `senders` to send a value or an error.
+///
+/// The output is `(index, value)` where `index` is the position of the winning sender in
+/// iteration order. Once a sender has won, stop is requested on all other senders. The receiver is
+/// only cancelled if all senders are cancelled, or if `senders` is empty.
+///
+/// ## Examples
+///
+/// ```
+/// use txrx::SenderExt;
+///
+/// let (index, value) = txrx::when_any_iter(vec![
+///     txrx::factories::cancelled().map(|_| 1).boxed(),
+///     txrx::just(2).boxed(),
+/// ])
+/// .sync_wait()
+/// .unwrap();
+/// assert_eq!((index, value), (1, 2));
+/// ```
+pub fn when_any_iter<I>(senders: I) -> WhenAnyIter<I::Item>
+where
+    I: IntoIterator,
+    I::Item: Sender,
+{
+    WhenAnyIter {
+        senders: senders.into_iter().collect(),
+    }
+}
+
+/// Sender returned by [`when_any()`](crate::SenderExt::when_any).
+pub struct WhenAny<Left, Right> {
+    left: Left,
+    right: Right,
+}
+
+impl<Left, Right> WhenAny<Left, Right> {
+    pub fn new(left: Left, right: Right) -> Self {
+        Self { left, right }
+    }
+}
+
+/// Sender returned by [`when_any_iter()`].
+pub struct WhenAnyIter<S> {
+    senders: Vec<S>,
+}
+
+struct Data<Next> {
+    next: Option<Next>,
+    remaining: usize,
+    // Forwards stop requests from `next` to all senders, dropped once `next` is completed.
+    stop_callback: Option<StopCallback>,
+}
+
+struct SharedState<Next> {
+    data: Mutex<Data<Next>>,
+    stop_source: StopSource,
+    env: Env,
+}
+
+impl<Next: Receiver> SharedState<Next> {
+    fn new(next: Next, remaining: usize) -> Arc<Self> {
+        let stop_source = StopSource::new();
+        let stop_callback = next.get_stop_token().on_stop({
+            let stop_source = stop_source.clone();
+            move || {
+                stop_source.request_stop();
+            }
+        });
+        let env = next.get_env();
+        Arc::new(Self {
+            data: Mutex::new(Data {
+                next: Some(next),
+                remaining,
+                stop_callback: Some(stop_callback),
+            }),
+            stop_source,
+            env,
+        })
+    }
+
+    // Takes `next` if this is the first completion, or if `last_only` is set and this is the
+    // last completion.
+    fn take_next(&self, last_only: bool) -> Option<Next> {
+        let (next, stop_callback) = {
+            let mut data = self.data.lock();
+            data.remaining -= 1;
+            if last_only && data.remaining > 0 {
+                return None;
+            }
+            (data.next.take(), data.stop_callback.take())
+        };
+        drop(stop_callback);
+        next
+    }
+
+    fn set_value(&self, value: Next::Input) {
+        if let Some(next) = self.take_next(false) {
+            // The race is decided, the losers can stop.
+            self.stop_source.request_stop();
+            next.set_value(value);
+        }
+    }
+
+    fn set_error(&self, error: Next::Error) {
+        if let Some(next) = self.take_next(false) {
+            self.stop_source.request_stop();
+            next.set_error(error);
+        }
+    }
+
+    fn set_cancelled(&self) {
+        if let Some(next) = self.take_next(true) {
+            next.set_cancelled();
+        }
+    }
+}
+
+struct ElementReceiver<Next: Receiver, T, E> {
+    state: Arc<SharedState<Next>>,
+    wrap: fn(usize, T) -> Next::Input,
+    index: usize,
+    _phantom: PhantomData<fn(E)>,
+}
+
+impl<Next: Receiver, T, E> ElementReceiver<Next, T, E> {
+    fn new(state: Arc<SharedState<Next>>, wrap: fn(usize, T) -> Next::Input, index: usize) -> Self {
+        Self {
+            state,
+            wrap,
+            index,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<Next, T, E> Receiver for ElementReceiver<Next, T, E>
+where
+    Next: Receiver,
+    E: Into<Next::Error>,
+{
+    type Input = T;
+    type Error = E;
+
+    fn set_value(self, value: Self::Input) {
+        self.state.set_value((self.wrap)(self.index, value));
+    }
+
+    fn set_error(self, error: Self::Error) {
+        self.state.set_error(error.into());
+    }
+
+    fn set_cancelled(self) {
+        self.state.set_cancelled();
+    }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+
+    fn get_env(&self) -> Env {
+        self.state.env.clone()
+    }
+}
+
+impl<Left, Right> Sender for WhenAny<Left, Right>
+where
+    Left: Sender,
+    Right: Sender,
+    Right::Error: Into<Left::Error>,
+{
+    type Output = Either<Left::Output, Right::Output>;
+    type Error = Left::Error;
+    type Scheduler = Left::Scheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        let state = SharedState::new(receiver, 2);
+        self.left.start(ElementReceiver::<_, _, Left::Error>::new(
+            state.clone(),
+            |_, value| Either::Left(value),
+            0,
+        ));
+        self.right.start(ElementReceiver::<_, _, Right::Error>::new(
+            state,
+            |_, value| Either::Right(value),
+            1,
+        ));
+    }
+}
+
+impl<S: Sender> Sender for WhenAnyIter<S> {
+    type Output = (usize, S::Output);
+    type Error = S::Error;
+    type Scheduler = S::Scheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        if self.senders.is_empty() {
+            receiver.set_cancelled();
+            return;
+        }
+
+        let state = SharedState::new(receiver, self.senders.len());
+        for (index, sender) in self.senders.into_iter().enumerate() {
+            sender.start(ElementReceiver::<_, _, S::Error>::new(
+                state.clone(),
+                |index, value| (index, value),
+                index,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::when_any_iter;
+    use crate::manual_executor::ManualExecutor;
+    use crate::test::ManualSender;
+    use crate::traits::Scheduler;
+    use crate::SenderExt;
+    use either::Either;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn first_value_wins() {
+        let executor = ManualExecutor::new();
+        let runner = executor.runner();
+        let (manual, trigger) = ManualSender::new();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let result = executor
+            .scheduler()
+            .schedule()
+            .map(|_| 1)
+            .upon_cancelled({
+                let cancelled = cancelled.clone();
+                move || {
+                    cancelled.store(true, Ordering::Relaxed);
+                    0
+                }
+            })
+            .when_any(manual.map(|_| "manual"))
+            .ensure_started();
+        trigger.trigger();
+        assert_eq!(result.sync_wait().unwrap(), Either::Right("manual"));
+
+        // The losing scheduled work observes the stop request and is cancelled.
+        assert!(!cancelled.load(Ordering::Relaxed));
+        assert!(runner.run_one());
+        assert!(cancelled.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn cancelled_only_if_all_cancelled() {
+        let result = crate::factories::cancelled()
+            .when_any(crate::factories::cancelled())
+            .sync_wait();
+        assert!(result.is_cancelled());
+
+        let result = crate::factories::cancelled()
+            .map(|_| 1)
+            .when_any(crate::factories::error("failed").map(|_: ()| 2))
+            .sync_wait();
+        assert_eq!(result.unwrap_error().to_string(), "failed");
+
+        assert!(when_any_iter(Vec::<crate::AnySender<()>>::new())
+            .sync_wait()
+            .is_cancelled());
+    }
+}
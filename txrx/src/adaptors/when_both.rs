@@ -1,44 +1,90 @@
-use crate::traits::{Receiver, Sender};
+use crate::adaptors::transfer::TransferJob;
+use crate::env::Env;
+use crate::priv_sync::Mutex;
+use crate::stop_token::{StopCallback, StopSource, StopToken};
+use crate::traits::{Channel, Connect, OperationState, Receiver, Scheduler, Sender, Work};
+use crate::utility::CompletionSchedulers;
+use core::marker::PhantomPinned;
+use core::pin::Pin;
 
 mod hidden {
+    use crate::adaptors::transfer::TransferJob;
+    use crate::env::Env;
     use crate::priv_sync::{Mutex, MutexGuard};
-    use crate::traits::{Receiver, Scheduler, Sender};
-    use std::sync::Arc;
+    use crate::stop_token::{StopCallback, StopSource, StopToken};
+    use crate::traits::{Channel, Receiver, Scheduler, Sender};
+    use crate::utility::CompletionSchedulers;
+    use alloc::sync::Arc;
 
     struct ReceiverSharedData<Left: Sender, Right: Sender, Next> {
         left_values: Option<Left::Output>,
         right_values: Option<Right::Output>,
         next: Option<Next>,
-        scheduler: Left::Scheduler,
+        // Completions from the right sender are moved to the completion scheduler of the left.
+        schedulers: CompletionSchedulers<Left::Scheduler>,
+        // Forwards stop requests from `next` to both senders, dropped once `next` is completed.
+        stop_callback: Option<StopCallback>,
     }
 
     impl<Left: Sender, Right: Sender, Next> ReceiverSharedData<Left, Right, Next> {
-        fn new(next: Next, scheduler: Left::Scheduler) -> Self {
+        fn new(next: Next, schedulers: CompletionSchedulers<Left::Scheduler>) -> Self {
             Self {
                 left_values: None,
                 right_values: None,
                 next: Some(next),
-                scheduler,
+                schedulers,
+                stop_callback: None,
             }
         }
+
+        fn take_next(&mut self) -> Option<Next> {
+            self.stop_callback = None;
+            self.next.take()
+        }
     }
 
     pub struct SharedState<Left: Sender, Right: Sender, Next> {
         state: Arc<Mutex<ReceiverSharedData<Left, Right, Next>>>,
+        stop_source: StopSource,
+        env: Env,
     }
 
-    impl<Left: Sender, Right: Sender, Next> SharedState<Left, Right, Next> {
-        pub fn new(next: Next, scheduler: Left::Scheduler) -> Self {
+    impl<Left: Sender, Right: Sender, Next: Receiver> SharedState<Left, Right, Next> {
+        pub fn new(next: Next, schedulers: CompletionSchedulers<Left::Scheduler>) -> Self {
+            let stop_source = StopSource::new();
+            let stop_callback = next.get_stop_token().on_stop({
+                let stop_source = stop_source.clone();
+                move || {
+                    stop_source.request_stop();
+                }
+            });
+            let env = next.get_env();
+            let mut data = ReceiverSharedData::new(next, schedulers);
+            data.stop_callback = Some(stop_callback);
             Self {
-                state: Arc::new(Mutex::new(ReceiverSharedData::new(next, scheduler))),
+                state: Arc::new(Mutex::new(data)),
+                stop_source,
+                env,
             }
         }
     }
 
+    impl<Left: Sender, Right: Sender, Next> SharedState<Left, Right, Next> {
+        pub fn get_stop_token(&self) -> StopToken {
+            self.stop_source.token()
+        }
+
+        pub fn get_env(&self) -> Env {
+            self.env.clone()
+        }
+    }
+
     impl<Left: Sender, Right: Sender, Next> Clone for SharedState<Left, Right, Next> {
         fn clone(&self) -> Self {
             Self {
                 state: self.state.clone(),
+                stop_source: self.stop_source.clone(),
+                env: self.env.clone(),
             }
         }
     }
@@ -47,30 +93,35 @@ mod hidden {
     where
         Left: Sender,
         Right: Sender,
-        Next: 'static + Send + Receiver<Input = (Left::Output, Right::Output)>,
+        Next: 'static + Send + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
     {
         pub fn set_cancelled(&self, use_scheduler: bool) {
             let mut lock = self.state.lock();
-            if let Some(next) = lock.next.take() {
-                let mut scheduler = lock.scheduler.clone();
+            if let Some(next) = lock.take_next() {
+                let scheduler = lock.schedulers.get(Channel::Cancelled);
                 drop(lock);
-                if use_scheduler {
-                    scheduler.execute(move || next.set_cancelled());
-                } else {
-                    next.set_cancelled();
+                // The other sender can stop since the result is already decided.
+                self.stop_source.request_stop();
+                match scheduler {
+                    Some(mut scheduler) if use_scheduler => {
+                        scheduler.execute(TransferJob::done(next))
+                    }
+                    _ => next.set_cancelled(),
                 }
             }
         }
 
-        pub fn set_error(&self, error: crate::Error, use_scheduler: bool) {
+        pub fn set_error(&self, error: Left::Error, use_scheduler: bool) {
             let mut lock = self.state.lock();
-            if let Some(next) = lock.next.take() {
-                let mut scheduler = lock.scheduler.clone();
+            if let Some(next) = lock.take_next() {
+                let scheduler = lock.schedulers.get(Channel::Error);
                 drop(lock);
-                if use_scheduler {
-                    scheduler.execute(move || next.set_error(error));
-                } else {
-                    next.set_error(error);
+                self.stop_source.request_stop();
+                match scheduler {
+                    Some(mut scheduler) if use_scheduler => {
+                        scheduler.execute(TransferJob::error(next, error))
+                    }
+                    _ => next.set_error(error),
                 }
             }
         }
@@ -99,17 +150,15 @@ mod hidden {
             mut lock: MutexGuard<ReceiverSharedData<Left, Right, Next>>,
             use_scheduler: bool,
         ) {
-            match (left.take(), right.take(), lock.next.take()) {
+            match (left.take(), right.take(), lock.take_next()) {
                 (Some(left), Some(right), Some(next)) => {
-                    if use_scheduler {
-                        let mut scheduler = lock.scheduler.clone();
-                        drop(lock);
-                        scheduler.execute(|| {
-                            next.set_value((left, right));
-                        });
-                    } else {
-                        drop(lock);
-                        next.set_value((left, right));
+                    let scheduler = lock.schedulers.get(Channel::Value);
+                    drop(lock);
+                    match scheduler {
+                        Some(mut scheduler) if use_scheduler => {
+                            scheduler.execute(TransferJob::value(next, (left, right)))
+                        }
+                        _ => next.set_value((left, right)),
                     }
                 }
                 _ => unreachable!("This code is unreachable!"),
@@ -134,16 +183,18 @@ impl<Left, Right> Sender for WhenBoth<Left, Right>
 where
     Left: 'static + Sender,
     Right: 'static + Sender,
+    Right::Error: Into<Left::Error>,
 {
     type Output = (Left::Output, Right::Output);
+    type Error = Left::Error;
     type Scheduler = Left::Scheduler;
 
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
-        let scheduler = self.left.get_scheduler();
-        let state = hidden::SharedState::<Left, Right, R>::new(receiver, scheduler);
+        let schedulers = CompletionSchedulers::new(&self.left);
+        let state = hidden::SharedState::<Left, Right, R>::new(receiver, schedulers);
         let left_receiver = LeftReceiver {
             state: state.clone(),
         };
@@ -154,8 +205,8 @@ where
         self.right.start(right_receiver);
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        self.left.get_scheduler()
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        self.left.get_completion_scheduler(channel)
     }
 }
 
@@ -167,21 +218,30 @@ impl<Left, Right, Next> Receiver for LeftReceiver<Left, Right, Next>
 where
     Left: Sender,
     Right: Sender,
-    Next: 'static + Send + Receiver<Input = (Left::Output, Right::Output)>,
+    Next: 'static + Send + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
 {
     type Input = Left::Output;
+    type Error = Left::Error;
 
     fn set_value(self, value: Self::Input) {
         self.state.set_value(Some(value), None);
     }
 
-    fn set_error(self, error: crate::Error) {
+    fn set_error(self, error: Self::Error) {
         self.state.set_error(error, false);
     }
 
     fn set_cancelled(self) {
         self.state.set_cancelled(false);
     }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.state.get_stop_token()
+    }
+
+    fn get_env(&self) -> Env {
+        self.state.get_env()
+    }
 }
 
 struct RightReceiver<Left: Sender, Right: Sender, Next> {
@@ -192,29 +252,397 @@ impl<Left, Right, Next> Receiver for RightReceiver<Left, Right, Next>
 where
     Left: Sender,
     Right: Sender,
-    Next: 'static + Send + Receiver<Input = (Left::Output, Right::Output)>,
+    Right::Error: Into<Left::Error>,
+    Next: 'static + Send + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
 {
     type Input = Right::Output;
+    type Error = Right::Error;
 
     fn set_value(self, value: Self::Input) {
         self.state.set_value(None, Some(value));
     }
 
-    fn set_error(self, error: crate::Error) {
-        self.state.set_error(error, true);
+    fn set_error(self, error: Self::Error) {
+        self.state.set_error(error.into(), true);
     }
 
     fn set_cancelled(self) {
         self.state.set_cancelled(true);
     }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.state.get_stop_token()
+    }
+
+    fn get_env(&self) -> Env {
+        self.state.get_env()
+    }
+}
+
+impl<Left, Right, R> Connect<R> for WhenBoth<Left, Right>
+where
+    Left: Sender + Connect<LeftOpReceiver<Left, Right, R>>,
+    Right: Sender + Connect<RightOpReceiver<Left, Right, R>>,
+    Right::Error: Into<Left::Error>,
+    R: 'static + Send + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
+{
+    type Operation = WhenBothOperation<Left, Right, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        let schedulers = CompletionSchedulers::new(&self.left);
+        let state = OpState {
+            next_token: receiver.get_stop_token(),
+            env: receiver.get_env(),
+            data: Mutex::new(OpData {
+                left: None,
+                right: None,
+                failure: None,
+                remaining: 2,
+                next: Some(receiver),
+                schedulers,
+                stop: None,
+                stop_requested: false,
+            }),
+        };
+        WhenBothOperation {
+            pending: Some((self.left, self.right)),
+            state,
+            left: None,
+            right: None,
+            _pinned: PhantomPinned,
+        }
+    }
+}
+
+/// Operation state of [`WhenBoth`], with the operation states of both senders stored inline.
+///
+/// Unlike when started as a [`Sender`], the receiver is only completed once both senders have
+/// completed, since their operation states are owned by this one. An error or cancellation
+/// still requests both senders to stop.
+pub struct WhenBothOperation<Left, Right, R>
+where
+    Left: Sender + Connect<LeftOpReceiver<Left, Right, R>>,
+    Right: Sender + Connect<RightOpReceiver<Left, Right, R>>,
+    R: 'static + Send + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
+{
+    // Connected when started, once the address of `state` is fixed.
+    pending: Option<(Left, Right)>,
+    state: OpState<Left, Right, R>,
+    left: Option<<Left as Connect<LeftOpReceiver<Left, Right, R>>>::Operation>,
+    right: Option<<Right as Connect<RightOpReceiver<Left, Right, R>>>::Operation>,
+    _pinned: PhantomPinned,
+}
+
+impl<Left, Right, R> OperationState for WhenBothOperation<Left, Right, R>
+where
+    Left: Sender + Connect<LeftOpReceiver<Left, Right, R>>,
+    Right: Sender + Connect<RightOpReceiver<Left, Right, R>>,
+    R: 'static + Send + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
+{
+    unsafe fn start(self: Pin<&mut Self>) {
+        let this = self.get_unchecked_mut();
+        if let Some((left, right)) = this.pending.take() {
+            let state: *const OpState<Left, Right, R> = &this.state;
+            let left = this.left.insert(left.connect(LeftOpReceiver::new(state)));
+            let right = this
+                .right
+                .insert(right.connect(RightOpReceiver::new(state)));
+            // Safety: both operation states are structurally pinned, `self` is. The receiver is
+            // completed once both have completed, so `self` stays alive while starting them.
+            Pin::new_unchecked(left).start();
+            Pin::new_unchecked(right).start();
+        }
+    }
+}
+
+enum Failure<E> {
+    Error(E),
+    Cancelled,
+    Abandoned,
+}
+
+enum OpCompletion<L, R, E> {
+    Left(L),
+    Right(R),
+    Failed(Failure<E>),
+}
+
+struct OpData<Left: Sender, Right: Sender, Next> {
+    left: Option<Left::Output>,
+    right: Option<Right::Output>,
+    // The first error, cancellation or abandonment decides the result.
+    failure: Option<Failure<Left::Error>>,
+    remaining: usize,
+    next: Option<Next>,
+    // Completions from the right sender are moved to the completion scheduler of the left.
+    schedulers: CompletionSchedulers<Left::Scheduler>,
+    // Created when one of the senders asks for a stop token, so that senders that never do
+    // don't allocate. Forwards stop requests from `next`.
+    stop: Option<(StopSource, StopCallback)>,
+    stop_requested: bool,
+}
+
+struct OpState<Left: Sender, Right: Sender, Next> {
+    data: Mutex<OpData<Left, Right, Next>>,
+    next_token: StopToken,
+    env: Env,
+}
+
+impl<Left, Right, Next> OpState<Left, Right, Next>
+where
+    Left: Sender,
+    Right: Sender,
+    Next: 'static + Send + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
+{
+    fn get_stop_token(&self) -> StopToken {
+        let mut data = self.data.lock();
+        if let Some((source, _)) = &data.stop {
+            return source.token();
+        }
+        let source = StopSource::new();
+        if data.stop_requested {
+            source.request_stop();
+        }
+        let callback = self.next_token.on_stop({
+            let source = source.clone();
+            move || {
+                source.request_stop();
+            }
+        });
+        let token = source.token();
+        data.stop = Some((source, callback));
+        token
+    }
+
+    fn complete(&self, completion: OpCompletion<Left::Output, Right::Output, Left::Error>) {
+        let from_right = matches!(completion, OpCompletion::Right(_));
+        let mut data = self.data.lock();
+        let mut stop_source = None;
+        match completion {
+            OpCompletion::Left(value) => data.left = Some(value),
+            OpCompletion::Right(value) => data.right = Some(value),
+            OpCompletion::Failed(failure) => {
+                if data.failure.is_none() {
+                    data.failure = Some(failure);
+                    data.stop_requested = true;
+                    stop_source = data.stop.as_ref().map(|(source, _)| source.clone());
+                }
+            }
+        }
+        data.remaining -= 1;
+        if data.remaining > 0 {
+            drop(data);
+            // The other sender can stop since the result is already decided.
+            if let Some(source) = stop_source {
+                source.request_stop();
+            }
+            return;
+        }
+
+        // Both senders have completed. Take everything out of `self` before completing `next`,
+        // which may drop the operation state.
+        let next = data.next.take();
+        let stop = data.stop.take();
+        let failure = data.failure.take();
+        let values = (data.left.take(), data.right.take());
+        let channel = match failure {
+            None => Channel::Value,
+            Some(Failure::Error(_)) => Channel::Error,
+            Some(_) => Channel::Cancelled,
+        };
+        let scheduler = data.schedulers.get(channel).filter(|_| from_right);
+        drop(data);
+        drop(stop);
+
+        let job = match (next, failure, values) {
+            // Dropping `next` passes the abandonment on.
+            (None, _, _) | (_, Some(Failure::Abandoned), _) => return,
+            (Some(next), Some(Failure::Error(error)), _) => TransferJob::error(next, error),
+            (Some(next), Some(Failure::Cancelled), _) => TransferJob::done(next),
+            (Some(next), None, (Some(left), Some(right))) => {
+                TransferJob::value(next, (left, right))
+            }
+            (Some(_), None, _) => unreachable!("Both senders completed with a value"),
+        };
+        match scheduler {
+            Some(mut scheduler) => scheduler.execute(job),
+            None => job.execute(),
+        }
+    }
+}
+
+macro_rules! op_receiver {
+    ($(#[$attr:meta])* $receiver:ident) => {
+        $(#[$attr])*
+        pub struct $receiver<Left, Right, Next>
+        where
+            Left: Sender,
+            Right: Sender,
+            Next: 'static
+                + Send
+                + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
+        {
+            // Points into the pinned `WhenBothOperation` that owns this receiver's operation.
+            state: *const OpState<Left, Right, Next>,
+            completed: bool,
+        }
+
+        // Safety: the state is only accessed through shared references.
+        unsafe impl<Left, Right, Next> Send for $receiver<Left, Right, Next>
+        where
+            Left: Sender,
+            Right: Sender,
+            Next: 'static
+                + Send
+                + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
+            OpState<Left, Right, Next>: Sync,
+        {
+        }
+
+        impl<Left, Right, Next> $receiver<Left, Right, Next>
+        where
+            Left: Sender,
+            Right: Sender,
+            Next: 'static
+                + Send
+                + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
+        {
+            fn new(state: *const OpState<Left, Right, Next>) -> Self {
+                Self {
+                    state,
+                    completed: false,
+                }
+            }
+
+            // Safety: the operation state outlives its receivers, see `WhenBothOperation::start()`.
+            fn state(&self) -> &OpState<Left, Right, Next> {
+                unsafe { &*self.state }
+            }
+
+            fn complete(
+                mut self,
+                completion: OpCompletion<Left::Output, Right::Output, Left::Error>,
+            ) {
+                self.completed = true;
+                self.state().complete(completion);
+            }
+        }
+
+        impl<Left, Right, Next> Drop for $receiver<Left, Right, Next>
+        where
+            Left: Sender,
+            Right: Sender,
+            Next: 'static
+                + Send
+                + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
+        {
+            fn drop(&mut self) {
+                if !self.completed {
+                    self.state()
+                        .complete(OpCompletion::Failed(Failure::Abandoned));
+                }
+            }
+        }
+    };
+}
+
+op_receiver!(
+    /// Receiver for the left sender in a [`WhenBothOperation`].
+    LeftOpReceiver
+);
+op_receiver!(
+    /// Receiver for the right sender in a [`WhenBothOperation`].
+    RightOpReceiver
+);
+
+impl<Left, Right, Next> Receiver for LeftOpReceiver<Left, Right, Next>
+where
+    Left: Sender,
+    Right: Sender,
+    Next: 'static + Send + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
+{
+    type Input = Left::Output;
+    type Error = Left::Error;
+
+    fn set_value(self, value: Self::Input) {
+        self.complete(OpCompletion::Left(value));
+    }
+
+    fn set_error(self, error: Self::Error) {
+        self.complete(OpCompletion::Failed(Failure::Error(error)));
+    }
+
+    fn set_cancelled(self) {
+        self.complete(OpCompletion::Failed(Failure::Cancelled));
+    }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.state().get_stop_token()
+    }
+
+    fn get_env(&self) -> Env {
+        self.state().env.clone()
+    }
+}
+
+impl<Left, Right, Next> Receiver for RightOpReceiver<Left, Right, Next>
+where
+    Left: Sender,
+    Right: Sender,
+    Right::Error: Into<Left::Error>,
+    Next: 'static + Send + Receiver<Input = (Left::Output, Right::Output), Error = Left::Error>,
+{
+    type Input = Right::Output;
+    type Error = Right::Error;
+
+    fn set_value(self, value: Self::Input) {
+        self.complete(OpCompletion::Right(value));
+    }
+
+    fn set_error(self, error: Self::Error) {
+        self.complete(OpCompletion::Failed(Failure::Error(error.into())));
+    }
+
+    fn set_cancelled(self) {
+        self.complete(OpCompletion::Failed(Failure::Cancelled));
+    }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.state().get_stop_token()
+    }
+
+    fn get_env(&self) -> Env {
+        self.state().env.clone()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::manual_executor::ManualExecutor;
     use crate::test::ManualSender;
-    use crate::traits::Scheduler;
+    use crate::traits::{Connect, FromSender, OperationState, Receiver, Scheduler};
     use crate::SenderExt;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+
+    struct RecordingReceiver(Arc<Mutex<Option<&'static str>>>);
+
+    impl Receiver for RecordingReceiver {
+        type Input = ((), ());
+        type Error = crate::Error;
+
+        fn set_value(self, _value: Self::Input) {
+            *self.0.lock().unwrap() = Some("value");
+        }
+
+        fn set_error(self, _error: Self::Error) {
+            *self.0.lock().unwrap() = Some("error");
+        }
+
+        fn set_cancelled(self) {
+            *self.0.lock().unwrap() = Some("cancelled");
+        }
+    }
 
     #[test]
     fn left_first() {
@@ -240,6 +668,32 @@ mod tests {
         assert!(fut.is_complete());
     }
 
+    #[test]
+    fn cancel_requests_stop() {
+        let exec = ManualExecutor::new();
+        let fut = exec
+            .scheduler()
+            .schedule()
+            .map(|_| panic!("Never called"))
+            .when_both(crate::factories::cancelled())
+            .ensure_started();
+        // The right sender cancels, which requests stop on the queued left sender.
+        assert!(exec.runner().run_one());
+        assert!(exec.runner().run_one());
+        assert!(fut.is_complete());
+        assert!(fut.sync_wait().is_cancelled());
+    }
+
+    #[test]
+    fn unknown_left_scheduler_completes_inline() {
+        let right = ManualExecutor::new();
+        let fut = crate::just(1)
+            .when_both(right.scheduler().schedule())
+            .ensure_started();
+        assert!(right.runner().run_one());
+        assert!(fut.is_complete());
+    }
+
     #[test]
     fn correct_scheduler() {
         let left = ManualExecutor::new();
@@ -273,4 +727,40 @@ mod tests {
             assert!(fut.is_complete());
         }
     }
+
+    #[test]
+    fn connected_error_waits_for_both() {
+        let exec = ManualExecutor::new();
+        let result = Arc::new(Mutex::new(None));
+        let mut operation = FromSender::new(exec.scheduler().schedule())
+            .map(|_| panic!("Never called"))
+            .when_both(FromSender::new(crate::factories::error(
+                crate::Error::from("failed"),
+            )))
+            .connect(RecordingReceiver(result.clone()));
+        // Safety: the executor is run to completion before the operation is dropped.
+        unsafe { Pin::new_unchecked(&mut operation).start() };
+        // The left sender still owns its part of the operation state.
+        assert_eq!(*result.lock().unwrap(), None);
+        // The error requested the left sender to stop.
+        assert!(exec.runner().run_one());
+        assert_eq!(*result.lock().unwrap(), Some("error"));
+    }
+
+    #[test]
+    fn shutdown_cancels_queued_continuation() {
+        let exec = ManualExecutor::new();
+        let (right, right_trigger) = ManualSender::new();
+        let fut = exec
+            .scheduler()
+            .schedule()
+            .when_both(right)
+            .ensure_started();
+        assert!(exec.runner().run_one());
+        // Completes on the left sender's scheduler.
+        right_trigger.trigger();
+        assert!(!fut.is_complete());
+        exec.shutdown();
+        assert!(fut.sync_wait().is_cancelled());
+    }
 }
@@ -0,0 +1,115 @@
+use crate::env::Env;
+use crate::stop_token::StopToken;
+use crate::traits::{Channel, Receiver, Sender};
+
+/// Sender that adds a value to the environment seen by the input sender.
+/// See [`with_env()`](crate::SenderExt::with_env) for details.
+pub struct WithEnv<S, T> {
+    sender: S,
+    value: T,
+}
+
+impl<S, T> WithEnv<S, T> {
+    pub fn new(sender: S, value: T) -> Self {
+        Self { sender, value }
+    }
+}
+
+impl<S, T> Sender for WithEnv<S, T>
+where
+    S: Sender,
+    T: 'static + Send + Sync,
+{
+    type Output = S::Output;
+    type Error = S::Error;
+    type Scheduler = S::Scheduler;
+
+    #[inline]
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        let env = receiver.get_env().with(self.value);
+        self.sender.start(WithEnvReceiver {
+            next: receiver,
+            env,
+        });
+    }
+
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        self.sender.get_completion_scheduler(channel)
+    }
+}
+
+pub struct WithEnvReceiver<Next> {
+    next: Next,
+    env: Env,
+}
+
+impl<Next: Receiver> Receiver for WithEnvReceiver<Next> {
+    type Input = Next::Input;
+    type Error = Next::Error;
+
+    #[inline]
+    fn set_value(self, value: Self::Input) {
+        self.next.set_value(value);
+    }
+
+    #[inline]
+    fn set_error(self, error: Self::Error) {
+        self.next.set_error(error);
+    }
+
+    #[inline]
+    fn set_cancelled(self) {
+        self.next.set_cancelled();
+    }
+
+    #[inline]
+    fn get_stop_token(&self) -> StopToken {
+        self.next.get_stop_token()
+    }
+
+    #[inline]
+    fn get_env(&self) -> Env {
+        self.env.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::factories::read_env;
+    use crate::SenderExt;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Span(&'static str);
+
+    #[test]
+    fn forwarded_through_adaptors() {
+        let exec = crate::manual_executor::ManualExecutor::new();
+        let fut = read_env::<Span>()
+            .transfer(exec.scheduler())
+            .map(|x| x)
+            .when_both(read_env::<u32>())
+            .and_then(|(span, id)| read_env::<Span>().map(move |inner| (span, id, inner)))
+            .bulk(1, |_, _| ())
+            .with_env(Span("outer"))
+            .with_env(5u32)
+            .ensure_started();
+        assert!(exec.runner().run_one());
+        let ((span, id, inner), _) = fut.sync_wait().unwrap();
+        assert_eq!(span, Some(Span("outer")));
+        assert_eq!(id, Some(5));
+        assert_eq!(inner, Some(Span("outer")));
+    }
+
+    #[test]
+    fn innermost_value_wins() {
+        let span = read_env::<Span>()
+            .with_env(Span("inner"))
+            .with_env(Span("outer"))
+            .sync_wait()
+            .unwrap();
+        assert_eq!(span, Some(Span("inner")));
+    }
+}
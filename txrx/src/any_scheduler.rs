@@ -0,0 +1,144 @@
+use crate::traits::{Channel, Receiver, Scheduler, Sender, Work};
+use alloc::boxed::Box;
+
+trait DynWork: Send {
+    fn dyn_execute(self: Box<Self>);
+    fn dyn_cancel(self: Box<Self>);
+}
+
+impl<W: Send + Work> DynWork for W {
+    fn dyn_execute(self: Box<Self>) {
+        (*self).execute();
+    }
+
+    fn dyn_cancel(self: Box<Self>) {
+        (*self).cancel();
+    }
+}
+
+// Forwards cancellation as well, which a boxed closure wouldn't.
+impl Work for Box<dyn DynWork> {
+    fn execute(self) {
+        self.dyn_execute();
+    }
+
+    fn cancel(self) {
+        self.dyn_cancel();
+    }
+}
+
+trait DynScheduler: Send {
+    fn dyn_clone(&self) -> Box<dyn DynScheduler>;
+    fn dyn_execute(&mut self, work: Box<dyn DynWork>);
+}
+
+impl<S: Scheduler> DynScheduler for S {
+    fn dyn_clone(&self) -> Box<dyn DynScheduler> {
+        Box::new(self.clone())
+    }
+
+    fn dyn_execute(&mut self, work: Box<dyn DynWork>) {
+        self.execute(work);
+    }
+}
+
+/// A type-erased scheduler.
+///
+/// All work is forwarded to the erased scheduler through [`Scheduler::execute()`].
+///
+/// ## Examples
+///
+/// ```
+/// use txrx::traits::Scheduler;
+/// use txrx::{AnyScheduler, ImmediateScheduler, SenderExt};
+///
+/// let mut scheduler = AnyScheduler::new(ImmediateScheduler);
+/// let value = scheduler.schedule().map(|_| 10).sync_wait().unwrap();
+/// assert_eq!(value, 10);
+/// ```
+pub struct AnyScheduler {
+    inner: Box<dyn DynScheduler>,
+}
+
+impl AnyScheduler {
+    pub fn new<S: Scheduler>(scheduler: S) -> Self {
+        Self {
+            inner: Box::new(scheduler),
+        }
+    }
+}
+
+impl Clone for AnyScheduler {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.dyn_clone(),
+        }
+    }
+}
+
+impl Scheduler for AnyScheduler {
+    type Sender = AnyScheduleSender;
+
+    fn schedule(&mut self) -> Self::Sender {
+        AnyScheduleSender {
+            scheduler: self.clone(),
+        }
+    }
+
+    fn execute<W>(&mut self, work: W)
+    where
+        W: 'static + Send + Work,
+    {
+        self.inner.dyn_execute(Box::new(work));
+    }
+}
+
+/// Sender returned by [`AnyScheduler::schedule()`](Scheduler::schedule).
+pub struct AnyScheduleSender {
+    scheduler: AnyScheduler,
+}
+
+impl Sender for AnyScheduleSender {
+    type Output = ();
+    type Error = crate::Error;
+    type Scheduler = AnyScheduler;
+
+    fn start<R>(mut self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        self.scheduler.execute(move || {
+            if receiver.get_stop_token().stop_requested() {
+                receiver.set_cancelled();
+            } else {
+                receiver.set_value(());
+            }
+        });
+    }
+
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(self.scheduler.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manual_executor::ManualExecutor;
+    use crate::traits::Scheduler;
+    use crate::{AnyScheduler, SenderExt};
+
+    #[test]
+    fn schedules_on_erased_scheduler() {
+        let exec = ManualExecutor::new();
+        let mut schedulers = [
+            AnyScheduler::new(exec.scheduler()),
+            AnyScheduler::new(crate::ImmediateScheduler),
+        ];
+        let fut = schedulers[0].schedule().map(|_| 1).ensure_started();
+        let immediate = schedulers[1].schedule().map(|_| 2).ensure_started();
+        assert!(immediate.is_complete());
+        assert!(!fut.is_complete());
+        assert!(exec.runner().run_one());
+        assert_eq!(fut.sync_wait().unwrap(), 1);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::any_scheduler::AnyScheduler;
+use crate::traits::receiver::DynReceiver;
+use crate::traits::{Channel, Receiver, Sender};
+use crate::utility::ReceiverRef;
+use alloc::boxed::Box;
+
+type BoxedReceiver<T, E> = Box<dyn Send + DynReceiver<Input = T, Error = E>>;
+
+trait DynSender<T, E>: Send {
+    fn dyn_start(self: Box<Self>, receiver: BoxedReceiver<T, E>);
+    fn dyn_get_completion_scheduler(&self, channel: Channel) -> Option<AnyScheduler>;
+}
+
+impl<S> DynSender<S::Output, S::Error> for S
+where
+    S: Send + Sender,
+{
+    fn dyn_start(self: Box<Self>, receiver: BoxedReceiver<S::Output, S::Error>) {
+        (*self).start(receiver);
+    }
+
+    fn dyn_get_completion_scheduler(&self, channel: Channel) -> Option<AnyScheduler> {
+        self.get_completion_scheduler(channel)
+            .map(AnyScheduler::new)
+    }
+}
+
+/// A type-erased sender.
+///
+/// `AnySender` can hold any `Send` sender with output `T` and error `E`, which makes it possible
+/// to store senders in struct fields or collections, and to return them from trait methods.
+/// Use [`boxed()`](crate::SenderExt::boxed) to create one.
+///
+/// The completion schedulers of the erased sender are reported as [`AnyScheduler`]s.
+///
+/// ## Examples
+///
+/// ```
+/// use txrx::{AnySender, SenderExt};
+///
+/// let senders: Vec<AnySender<i32>> = vec![
+///     txrx::just(1).boxed(),
+///     txrx::just(2).map(|x| x * 2).boxed(),
+/// ];
+///
+/// let results: Vec<i32> = senders.into_iter().map(|s| s.sync_wait().unwrap()).collect();
+/// assert_eq!(results, vec![1, 4]);
+/// ```
+pub struct AnySender<T, E = crate::Error> {
+    inner: Box<dyn DynSender<T, E>>,
+}
+
+impl<T, E> AnySender<T, E>
+where
+    T: 'static + Send,
+    E: 'static + Send,
+{
+    pub fn new<S>(sender: S) -> Self
+    where
+        S: 'static + Send + Sender<Output = T, Error = E>,
+    {
+        Self {
+            inner: Box::new(sender),
+        }
+    }
+}
+
+impl<T, E> Sender for AnySender<T, E>
+where
+    T: 'static + Send,
+    E: 'static + Send,
+{
+    type Output = T;
+    type Error = E;
+    type Scheduler = AnyScheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        self.inner.dyn_start(Box::new(ReceiverRef::new(receiver)));
+    }
+
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        self.inner.dyn_get_completion_scheduler(channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manual_executor::ManualExecutor;
+    use crate::traits::{Channel, Scheduler, Sender};
+    use crate::{AnySender, SenderExt};
+
+    struct Pipeline {
+        sender: AnySender<String>,
+    }
+
+    #[test]
+    fn store_in_struct() {
+        let exec = ManualExecutor::new();
+        let other = ManualExecutor::new();
+        let pipeline = Pipeline {
+            sender: exec
+                .scheduler()
+                .schedule()
+                .map(|_| 10)
+                .and_then(|x| crate::just(x + 1))
+                .transfer(other.scheduler())
+                .map(|x: i32| x.to_string())
+                .boxed(),
+        };
+        assert!(pipeline
+            .sender
+            .get_completion_scheduler(Channel::Value)
+            .is_some());
+
+        let fut = pipeline
+            .sender
+            .bulk(2, |i, _| i)
+            .map(|(value, steps)| format!("{}: {:?}", value, steps))
+            .ensure_started();
+        assert!(exec.runner().run_one());
+        assert!(!fut.is_complete());
+        // The transfer, then the first bulk step is scheduled on the completion scheduler of the
+        // transfer while the last step runs inline.
+        assert!(other.runner().run_one());
+        assert!(!fut.is_complete());
+        assert!(other.runner().run_one());
+        assert!(fut.is_complete());
+        assert_eq!(fut.sync_wait().unwrap(), "11: [0, 1]");
+    }
+
+    #[test]
+    fn errors_and_cancellation() {
+        let error: AnySender<(), i32> = crate::factories::error(5).boxed();
+        assert_eq!(error.sync_wait().unwrap_error(), 5);
+        assert!(crate::factories::cancelled()
+            .boxed()
+            .sync_wait()
+            .is_cancelled());
+        assert!(crate::just(())
+            .boxed()
+            .get_completion_scheduler(Channel::Value)
+            .is_none());
+    }
+}
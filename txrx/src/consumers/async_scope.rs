@@ -0,0 +1,309 @@
+//! Tracking of detached work.
+//!
+//! See [`AsyncScope`] for details.
+use crate::priv_sync::Mutex;
+use crate::stop_token::{StopSource, StopToken};
+use crate::traits::receiver::DynReceiver;
+use crate::traits::{Receiver, Sender};
+use crate::utility::ReceiverRef;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+type ErrorHandler = Box<dyn Fn(crate::Error) + Send + Sync>;
+type Waiter = Box<dyn Send + DynReceiver<Input = (), Error = crate::Error>>;
+
+struct Data {
+    outstanding: usize,
+    errors: Vec<crate::Error>,
+    waiters: Vec<Waiter>,
+}
+
+struct Inner {
+    data: Mutex<Data>,
+    stop_source: StopSource,
+    error_handler: Option<ErrorHandler>,
+}
+
+impl Inner {
+    fn on_error(&self, error: crate::Error) {
+        match &self.error_handler {
+            Some(handler) => handler(error),
+            None => self.data.lock().errors.push(error),
+        }
+    }
+
+    fn on_complete(&self) {
+        let waiters = {
+            let mut data = self.data.lock();
+            data.outstanding -= 1;
+            if data.outstanding > 0 {
+                return;
+            }
+            core::mem::take(&mut data.waiters)
+        };
+        for waiter in waiters {
+            waiter.set_value(());
+        }
+    }
+}
+
+/// A scope that detached work can be spawned into.
+///
+/// Unlike [`start_detached()`](crate::start_detached), the scope keeps track of how many spawned
+/// senders are still running, handles their errors and can ask all of them to stop. Use
+/// [`on_empty()`](AsyncScope::on_empty) to wait for all spawned work to finish, for instance at
+/// shutdown.
+///
+/// Errors are collected and can be retrieved with [`take_errors()`](AsyncScope::take_errors),
+/// unless the scope was created with an error handler.
+///
+/// Cloning an `AsyncScope` returns a handle to the same scope.
+///
+/// ## Examples
+///
+/// ```
+/// use txrx::{AsyncScope, SenderExt};
+///
+/// let scope = AsyncScope::new();
+/// scope.spawn(txrx::just(1).map(|_| ()));
+/// scope.spawn(txrx::factories::error("failed"));
+///
+/// scope.on_empty().sync_wait().unwrap();
+/// assert_eq!(scope.outstanding(), 0);
+/// assert_eq!(scope.take_errors().len(), 1);
+/// ```
+#[derive(Clone)]
+pub struct AsyncScope {
+    inner: Arc<Inner>,
+}
+
+impl Default for AsyncScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsyncScope {
+    /// Creates a scope that collects the errors of spawned senders.
+    pub fn new() -> Self {
+        Self::create(None)
+    }
+
+    /// Creates a scope that invokes `handler` with the errors of spawned senders.
+    ///
+    /// The handler is invoked on whatever context the failing sender completes on.
+    pub fn with_error_handler<F>(handler: F) -> Self
+    where
+        F: 'static + Send + Sync + Fn(crate::Error),
+    {
+        Self::create(Some(Box::new(handler)))
+    }
+
+    fn create(error_handler: Option<ErrorHandler>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                data: Mutex::new(Data {
+                    outstanding: 0,
+                    errors: Vec::new(),
+                    waiters: Vec::new(),
+                }),
+                stop_source: StopSource::new(),
+                error_handler,
+            }),
+        }
+    }
+
+    /// Starts `sender` and tracks it until it completes.
+    ///
+    /// The sender sees the stop token of the scope, so it is asked to stop by
+    /// [`request_stop()`](AsyncScope::request_stop).
+    pub fn spawn<S>(&self, sender: S)
+    where
+        S: 'static + Send + Sender,
+        S::Error: Into<crate::Error>,
+    {
+        self.inner.data.lock().outstanding += 1;
+        sender.start(SpawnReceiver {
+            inner: self.inner.clone(),
+            completed: false,
+            _phantom: PhantomData::<fn(S)>,
+        });
+    }
+
+    /// Returns the number of spawned senders that haven't completed yet.
+    pub fn outstanding(&self) -> usize {
+        self.inner.data.lock().outstanding
+    }
+
+    /// Requests stop on all spawned senders, including senders spawned after this call.
+    ///
+    /// Returns `true` if this call made the stop request.
+    pub fn request_stop(&self) -> bool {
+        self.inner.stop_source.request_stop()
+    }
+
+    /// Returns the stop token seen by spawned senders, which is stopped by
+    /// [`request_stop()`](AsyncScope::request_stop).
+    pub fn stop_token(&self) -> StopToken {
+        self.inner.stop_source.token()
+    }
+
+    /// Returns the errors collected so far. Always empty if the scope has an error handler.
+    pub fn take_errors(&self) -> Vec<crate::Error> {
+        core::mem::take(&mut self.inner.data.lock().errors)
+    }
+
+    /// Returns a sender that completes once no spawned senders are outstanding.
+    ///
+    /// If nothing is outstanding when the sender is started it completes immediately.
+    pub fn on_empty(&self) -> OnEmpty {
+        OnEmpty {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+struct SpawnReceiver<S> {
+    inner: Arc<Inner>,
+    completed: bool,
+    _phantom: PhantomData<fn(S)>,
+}
+
+impl<S> SpawnReceiver<S> {
+    fn complete(mut self) {
+        self.completed = true;
+        self.inner.on_complete();
+    }
+}
+
+// A sender that drops its receiver without completing it is no longer outstanding either.
+impl<S> Drop for SpawnReceiver<S> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.inner.on_complete();
+        }
+    }
+}
+
+impl<S> Receiver for SpawnReceiver<S>
+where
+    S: Sender,
+    S::Error: Into<crate::Error>,
+{
+    type Input = S::Output;
+    type Error = S::Error;
+
+    fn set_value(self, _value: Self::Input) {
+        self.complete();
+    }
+
+    fn set_error(self, error: Self::Error) {
+        self.inner.on_error(error.into());
+        self.complete();
+    }
+
+    fn set_cancelled(self) {
+        self.complete();
+    }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.inner.stop_source.token()
+    }
+}
+
+/// Sender returned by [`AsyncScope::on_empty()`].
+pub struct OnEmpty {
+    inner: Arc<Inner>,
+}
+
+impl Sender for OnEmpty {
+    type Output = ();
+    type Error = crate::Error;
+    type Scheduler = crate::ImmediateScheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        let mut data = self.inner.data.lock();
+        if data.outstanding == 0 {
+            drop(data);
+            receiver.set_value(());
+        } else {
+            data.waiters.push(Box::new(ReceiverRef::new(receiver)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncScope;
+    use crate::manual_executor::ManualExecutor;
+    use crate::test::ManualSender;
+    use crate::traits::Scheduler;
+    use crate::SenderExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn outstanding_and_on_empty() {
+        let scope = AsyncScope::new();
+        let (first, first_trigger) = ManualSender::new();
+        let (second, second_trigger) = ManualSender::new();
+        scope.spawn(first);
+        scope.spawn(second.and_then(|_| crate::factories::error("failed")));
+        assert_eq!(scope.outstanding(), 2);
+
+        let empty = scope.on_empty().ensure_started();
+        first_trigger.trigger();
+        assert_eq!(scope.outstanding(), 1);
+        second_trigger.trigger();
+        assert!(empty.sync_wait().is_value());
+        let errors = scope.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].to_string(), "failed");
+    }
+
+    #[test]
+    fn error_handler_and_stop() {
+        let errors = Arc::new(AtomicUsize::new(0));
+        let scope = AsyncScope::with_error_handler({
+            let errors = errors.clone();
+            move |_| {
+                errors.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        let executor = ManualExecutor::new();
+        let runner = executor.runner();
+        scope.spawn(crate::factories::error("failed"));
+        scope.spawn(
+            executor
+                .scheduler()
+                .schedule()
+                .and_then(|_| crate::factories::error("not reached")),
+        );
+        assert_eq!(errors.load(Ordering::Relaxed), 1);
+
+        assert!(scope.request_stop());
+        assert!(runner.run_one());
+        assert_eq!(scope.outstanding(), 0);
+        assert_eq!(errors.load(Ordering::Relaxed), 1);
+        assert!(scope.take_errors().is_empty());
+    }
+
+    #[test]
+    fn dropped_receiver_is_not_outstanding() {
+        let scope = AsyncScope::new();
+        let (sender, trigger) = ManualSender::new();
+        scope.spawn(sender);
+        let empty = scope.on_empty().ensure_started();
+        assert_eq!(scope.outstanding(), 1);
+
+        // Dropping the trigger drops the receiver without completing it.
+        drop(trigger);
+        assert_eq!(scope.outstanding(), 0);
+        assert!(empty.sync_wait().is_value());
+    }
+}
@@ -1,83 +1,103 @@
+use crate::consumers::sync_wait::{BrokenPromise, WaitResult};
 use crate::priv_sync::Mutex;
 use crate::traits::{Receiver, Sender};
 
-use std::pin::Pin;
-use std::sync::Arc;
-use std::task::{Context, Poll, Waker};
+use alloc::sync::Arc;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
 
-struct SharedStateData<T> {
+struct SharedStateData<T, E> {
     waker: Option<Waker>,
-    result: Option<crate::Result<T>>,
+    result: Option<WaitResult<T, E>>,
 }
 
-impl<T> SharedStateData<T> {
+impl<T, E> SharedStateData<T, E> {
     fn new() -> Self {
         Self {
             waker: None,
             result: None,
         }
     }
-    fn wakeup(&mut self) {
+
+    fn set_result(&mut self, result: WaitResult<T, E>) {
+        self.result = Some(result);
         if let Some(waker) = self.waker.take() {
             waker.wake();
         }
     }
-    fn set_value(&mut self, value: T) {
-        self.result = Some(Ok(Some(value)));
-        self.wakeup();
-    }
-
-    fn set_error(&mut self, error: crate::Error) {
-        self.result = Some(Err(error));
-        self.wakeup();
-    }
-
-    fn set_cancelled(&mut self) {
-        self.result = Some(Ok(None));
-        self.wakeup();
-    }
 }
 
-struct SharedState<T> {
-    data: Mutex<SharedStateData<T>>,
+struct SharedState<T, E> {
+    data: Mutex<SharedStateData<T, E>>,
 }
 
-impl<T> SharedState<T> {
+impl<T, E> SharedState<T, E> {
     fn new() -> Self {
         Self {
             data: Mutex::new(SharedStateData::new()),
         }
     }
+
+    fn poll(&self, cx: &mut Context<'_>) -> Poll<WaitResult<T, E>> {
+        let mut lock = self.data.lock();
+        if let Some(data) = lock.result.take() {
+            Poll::Ready(data)
+        } else {
+            lock.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
 }
 
-struct AwaitableReceiver<T> {
-    state: Arc<SharedState<T>>,
+struct AwaitableReceiver<T, E> {
+    // Taken when the receiver is completed, reports abandonment if still set on drop.
+    state: Option<Arc<SharedState<T, E>>>,
 }
 
-impl<T> AwaitableReceiver<T> {
-    fn new(state: Arc<SharedState<T>>) -> Self {
-        Self { state }
+impl<T, E> AwaitableReceiver<T, E> {
+    fn new(state: Arc<SharedState<T, E>>) -> Self {
+        Self { state: Some(state) }
+    }
+
+    fn set_result(mut self, result: WaitResult<T, E>) {
+        if let Some(state) = self.state.take() {
+            state.data.lock().set_result(result);
+        }
     }
 }
 
-impl<T> Receiver for AwaitableReceiver<T> {
+impl<T, E> Drop for AwaitableReceiver<T, E> {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            state.data.lock().set_result(WaitResult::Abandoned);
+        }
+    }
+}
+
+impl<T, E> Receiver for AwaitableReceiver<T, E> {
     type Input = T;
+    type Error = E;
 
     fn set_value(self, value: Self::Input) {
-        self.state.data.lock().set_value(value);
+        self.set_result(WaitResult::Value(value));
     }
 
-    fn set_error(self, error: crate::Error) {
-        self.state.data.lock().set_error(error);
+    fn set_error(self, error: Self::Error) {
+        self.set_result(WaitResult::Error(error));
     }
 
     fn set_cancelled(self) {
-        self.state.data.lock().set_cancelled();
+        self.set_result(WaitResult::Cancelled);
     }
 }
 
+/// Future returned by [`into_awaitable()`](crate::SenderExt::into_awaitable).
+///
+/// Resolves to a [`BrokenPromise`] error if the receiver is dropped without being completed, see
+/// [`WaitResult::into_result()`]. Use [`into_wait_result()`](Awaitable::into_wait_result) to
+/// tell all outcomes apart.
 pub struct Awaitable<S: Sender> {
-    shared_state: Arc<SharedState<S::Output>>,
+    shared_state: Arc<SharedState<S::Output, S::Error>>,
 }
 
 impl<S: Sender> Awaitable<S> {
@@ -86,20 +106,64 @@ impl<S: Sender> Awaitable<S> {
         sender.start(AwaitableReceiver::new(shared_state.clone()));
         Self { shared_state }
     }
+
+    /// Returns a future that resolves to the [`WaitResult`] instead.
+    pub fn into_wait_result(self) -> WaitResultAwaitable<S> {
+        WaitResultAwaitable {
+            shared_state: self.shared_state,
+        }
+    }
 }
 
-impl<S: Sender> std::future::Future for Awaitable<S> {
-    type Output = crate::Result<S::Output>;
+impl<S: Sender> core::future::Future for Awaitable<S>
+where
+    S::Error: From<BrokenPromise>,
+{
+    type Output = crate::Result<S::Output, S::Error>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let me = unsafe { self.get_unchecked_mut() };
+        self.shared_state.poll(cx).map(WaitResult::into_result)
+    }
+}
 
-        let mut lock = me.shared_state.data.lock();
-        if let Some(data) = lock.result.take() {
-            Poll::Ready(data)
-        } else {
-            lock.waker = Some(cx.waker().clone());
-            Poll::Pending
-        }
+/// Future returned by [`Awaitable::into_wait_result()`].
+pub struct WaitResultAwaitable<S: Sender> {
+    shared_state: Arc<SharedState<S::Output, S::Error>>,
+}
+
+impl<S: Sender> core::future::Future for WaitResultAwaitable<S> {
+    type Output = WaitResult<S::Output, S::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.shared_state.poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::consumers::sync_wait::BrokenPromise;
+    use crate::test::ManualSender;
+    use crate::{from_future, ImmediateScheduler, SenderExt};
+
+    #[test]
+    fn abandoned() {
+        let (sender, trigger) = ManualSender::new();
+        let awaitable = sender.into_awaitable();
+        drop(trigger);
+        let result = from_future(ImmediateScheduler, awaitable)
+            .sync_wait()
+            .unwrap();
+        assert!(result
+            .unwrap_err()
+            .downcast_ref::<BrokenPromise>()
+            .is_some());
+
+        let (sender, trigger) = ManualSender::new();
+        let awaitable = sender.into_awaitable().into_wait_result();
+        drop(trigger);
+        let result = from_future(ImmediateScheduler, awaitable)
+            .sync_wait()
+            .unwrap();
+        assert!(result.is_abandoned());
     }
 }
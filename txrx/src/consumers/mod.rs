@@ -1,6 +1,8 @@
-pub use start_detached::start_detached;
+pub use start_detached::{start_detached, start_detached_with};
+#[cfg(feature = "std")]
 pub use sync_wait::sync_wait;
 
+pub mod async_scope;
 pub mod into_awaitable;
 pub mod start_detached;
 pub mod sync_wait;
@@ -1,18 +1,94 @@
+//! Starting senders without waiting for their result.
+//!
+//! Errors from senders started with [`start_detached()`] are reported to a process-wide hook. By
+//! default the error is printed to stderr when the `std` feature is enabled, and ignored
+//! otherwise. Use [`set_detached_error_hook()`] to log, count or ignore errors in some other way,
+//! or [`start_detached_with()`] to handle the errors of a single sender.
+use crate::priv_sync::Mutex;
 use crate::traits::{Receiver, Sender};
-use std::marker::PhantomData;
+use alloc::sync::Arc;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
-struct DropPanic;
+type ErrorHook = Arc<dyn Fn(crate::Error) + Send + Sync>;
 
-impl Drop for DropPanic {
-    fn drop(&mut self) {
-        panic!("Forced drop!");
+static ERROR_HOOK: Mutex<Option<ErrorHook>> = Mutex::new(None);
+static ERROR_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the hook that is invoked with errors from senders started with [`start_detached()`].
+///
+/// The hook is invoked on whatever context the failing sender completes on.
+pub fn set_detached_error_hook<F>(hook: F)
+where
+    F: 'static + Send + Sync + Fn(crate::Error),
+{
+    *ERROR_HOOK.lock() = Some(Arc::new(hook));
+}
+
+/// Restores the default hook for errors from detached senders.
+pub fn reset_detached_error_hook() {
+    *ERROR_HOOK.lock() = None;
+}
+
+/// Returns the total number of errors that have been reported to the detached error hook.
+pub fn detached_error_count() -> usize {
+    ERROR_COUNT.load(Ordering::Relaxed)
+}
+
+fn report_error(error: crate::Error) {
+    ERROR_COUNT.fetch_add(1, Ordering::Relaxed);
+    // Invoke the hook outside of the lock, it is free to replace itself.
+    let hook = ERROR_HOOK.lock().clone();
+    match hook {
+        Some(hook) => hook(error),
+        None => default_hook(error),
     }
 }
 
-pub fn start_detached<S: 'static + Send + Sender>(sender: S) {
+#[cfg(feature = "std")]
+fn default_hook(error: crate::Error) {
+    std::eprintln!("txrx: detached sender failed: {}", error);
+}
+
+#[cfg(not(feature = "std"))]
+fn default_hook(_error: crate::Error) {}
+
+/// Starts `sender` without waiting for it to complete.
+///
+/// Values and cancellation are ignored, errors are reported to the detached error hook. See the
+/// [module documentation](crate::consumers::start_detached) for details.
+pub fn start_detached<S>(sender: S)
+where
+    S: 'static + Send + Sender,
+    S::Error: Into<crate::Error>,
+{
     sender.start(SinkFor::<S>::new());
 }
 
+/// Starts `sender` without waiting for it to complete, invoking `error_handler` if it fails.
+///
+/// ## Examples
+///
+/// ```
+/// use std::sync::mpsc::channel;
+///
+/// let (tx, rx) = channel();
+/// txrx::consumers::start_detached_with(txrx::factories::error(10), move |error| {
+///     tx.send(error).unwrap();
+/// });
+/// assert_eq!(rx.recv().unwrap(), 10);
+/// ```
+pub fn start_detached_with<S, F>(sender: S, error_handler: F)
+where
+    S: 'static + Send + Sender,
+    F: 'static + Send + FnOnce(S::Error),
+{
+    sender.start(HandlerSink {
+        error_handler,
+        _p: PhantomData::<fn(S)>,
+    });
+}
+
 pub struct SinkFor<S> {
     _p: PhantomData<S>,
 }
@@ -23,15 +99,84 @@ impl<S> SinkFor<S> {
     }
 }
 
-impl<S: 'static + Send + Sender> Receiver for SinkFor<S> {
+impl<S> Receiver for SinkFor<S>
+where
+    S: 'static + Send + Sender,
+    S::Error: Into<crate::Error>,
+{
+    type Input = S::Output;
+    type Error = S::Error;
+
+    fn set_value(self, _value: Self::Input) {}
+
+    fn set_error(self, error: Self::Error) {
+        report_error(error.into());
+    }
+
+    fn set_cancelled(self) {}
+}
+
+struct HandlerSink<S, F> {
+    error_handler: F,
+    _p: PhantomData<fn(S)>,
+}
+
+impl<S, F> Receiver for HandlerSink<S, F>
+where
+    S: Sender,
+    F: FnOnce(S::Error),
+{
     type Input = S::Output;
+    type Error = S::Error;
 
     fn set_value(self, _value: Self::Input) {}
 
-    fn set_error(self, _error: crate::Error) {
-        let _drop = DropPanic;
-        panic!("Sink error!");
+    fn set_error(self, error: Self::Error) {
+        (self.error_handler)(error);
     }
 
     fn set_cancelled(self) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+    use std::sync::atomic::AtomicBool;
+
+    #[test]
+    fn error_handler() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        start_detached_with(crate::just(1), {
+            let tx = tx.clone();
+            move |error| tx.send(error.to_string()).unwrap()
+        });
+        start_detached_with(crate::factories::error("failed"), move |error| {
+            tx.send(error.to_string()).unwrap()
+        });
+        assert_eq!(rx.iter().collect::<std::vec::Vec<_>>(), ["failed"]);
+    }
+
+    // The only test that replaces the process-wide hook, so no other test needs to serialize
+    // with it.
+    #[test]
+    fn error_hook() {
+        let called = Arc::new(AtomicBool::new(false));
+        set_detached_error_hook({
+            let called = called.clone();
+            // Other tests may start failing senders concurrently, only look for our own error.
+            move |error| {
+                if error.to_string() == "error_hook failed" {
+                    called.store(true, Ordering::Relaxed);
+                }
+            }
+        });
+        let count = detached_error_count();
+        start_detached(crate::just(1));
+        start_detached(crate::factories::error("error_hook failed"));
+        reset_detached_error_hook();
+
+        assert!(called.load(Ordering::Relaxed));
+        assert!(detached_error_count() > count);
+    }
+}
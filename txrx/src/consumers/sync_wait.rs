@@ -1,15 +1,37 @@
-use crate::priv_sync::AsyncValue;
-use crate::traits::{Receiver, Sender};
-use std::sync::Arc;
+#[cfg(feature = "std")]
+pub use blocking::{sync_wait, sync_wait_timeout, sync_wait_with_stop, Recv, State};
 
+use core::fmt;
+
+/// The result of waiting for a sender to complete.
+///
+/// Convert it into a `Result<V, WaitError<E>>` to use it with `?`:
+///
+/// ```
+/// use txrx::consumers::sync_wait::WaitError;
+/// use txrx::SenderExt;
+///
+/// fn double() -> Result<i32, WaitError> {
+///     let value = txrx::just(10).sync_wait().into_std_result()?;
+///     Ok(value * 2)
+/// }
+///
+/// assert_eq!(double().unwrap(), 20);
+/// ```
 #[derive(Debug)]
-pub enum WaitResult<V> {
+pub enum WaitResult<V, E = crate::Error> {
     Value(V),
-    Error(crate::Error),
+    Error(E),
     Cancelled,
+    /// The receiver was dropped without being completed, for example because the sender was
+    /// dropped by a scheduler or a closure panicked.
+    Abandoned,
+    /// The sender didn't complete in time, see
+    /// [`sync_wait_timeout()`](crate::consumers::sync_wait::sync_wait_timeout).
+    TimedOut,
 }
 
-impl<V> WaitResult<V> {
+impl<V, E> WaitResult<V, E> {
     pub fn unwrap(self) -> V {
         match self {
             WaitResult::Value(v) => v,
@@ -17,13 +39,21 @@ impl<V> WaitResult<V> {
         }
     }
 
-    pub fn unwrap_error(self) -> crate::Error {
+    pub fn unwrap_error(self) -> E {
         match self {
             WaitResult::Error(v) => v,
             _ => panic!("Result does not contain an error"),
         }
     }
 
+    /// Returns the value, panicking with `message` if there is none.
+    pub fn expect(self, message: &str) -> V {
+        match self {
+            WaitResult::Value(v) => v,
+            _ => panic!("{}", message),
+        }
+    }
+
     pub fn unwrap_cancelled(self) {
         match self {
             WaitResult::Cancelled => {}
@@ -31,11 +61,65 @@ impl<V> WaitResult<V> {
         }
     }
 
-    pub fn into_result(self) -> crate::Result<V> {
+    /// Converts into a [`crate::Result`], where cancellation and timeouts are `Ok(None)` and
+    /// abandonment is a [`BrokenPromise`] error.
+    ///
+    /// Use [`into_std_result()`](WaitResult::into_std_result) to tell all outcomes apart.
+    pub fn into_result(self) -> crate::Result<V, E>
+    where
+        E: From<BrokenPromise>,
+    {
         match self {
             WaitResult::Value(v) => Ok(Some(v)),
             WaitResult::Error(e) => Err(e),
-            WaitResult::Cancelled => Ok(None),
+            WaitResult::Abandoned => Err(BrokenPromise.into()),
+            WaitResult::Cancelled | WaitResult::TimedOut => Ok(None),
+        }
+    }
+
+    /// Converts into a `Result` where everything but a value is a [`WaitError`].
+    pub fn into_std_result(self) -> Result<V, WaitError<E>> {
+        self.into()
+    }
+
+    /// Returns the value, if any.
+    pub fn ok(self) -> Option<V> {
+        match self {
+            WaitResult::Value(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Maps the value with `func`, leaving the other variants untouched.
+    pub fn map<U, F: FnOnce(V) -> U>(self, func: F) -> WaitResult<U, E> {
+        match self {
+            WaitResult::Value(v) => WaitResult::Value(func(v)),
+            WaitResult::Error(e) => WaitResult::Error(e),
+            WaitResult::Cancelled => WaitResult::Cancelled,
+            WaitResult::Abandoned => WaitResult::Abandoned,
+            WaitResult::TimedOut => WaitResult::TimedOut,
+        }
+    }
+
+    /// Maps the error with `func`, leaving the other variants untouched.
+    pub fn map_err<F, Func: FnOnce(E) -> F>(self, func: Func) -> WaitResult<V, F> {
+        match self {
+            WaitResult::Value(v) => WaitResult::Value(v),
+            WaitResult::Error(e) => WaitResult::Error(func(e)),
+            WaitResult::Cancelled => WaitResult::Cancelled,
+            WaitResult::Abandoned => WaitResult::Abandoned,
+            WaitResult::TimedOut => WaitResult::TimedOut,
+        }
+    }
+
+    /// Borrows the value or the error.
+    pub fn as_ref(&self) -> WaitResult<&V, &E> {
+        match self {
+            WaitResult::Value(v) => WaitResult::Value(v),
+            WaitResult::Error(e) => WaitResult::Error(e),
+            WaitResult::Cancelled => WaitResult::Cancelled,
+            WaitResult::Abandoned => WaitResult::Abandoned,
+            WaitResult::TimedOut => WaitResult::TimedOut,
         }
     }
 
@@ -43,71 +127,411 @@ impl<V> WaitResult<V> {
         matches!(self, WaitResult::Value(_))
     }
 
+    pub fn is_error(&self) -> bool {
+        matches!(self, WaitResult::Error(_))
+    }
+
     pub fn is_cancelled(&self) -> bool {
         matches!(self, WaitResult::Cancelled)
     }
+
+    pub fn is_abandoned(&self) -> bool {
+        matches!(self, WaitResult::Abandoned)
+    }
+
+    pub fn is_timed_out(&self) -> bool {
+        matches!(self, WaitResult::TimedOut)
+    }
 }
 
-impl<V> From<crate::Result<V>> for WaitResult<V> {
-    fn from(r: crate::Result<V>) -> Self {
-        match r {
-            Ok(Some(v)) => Self::Value(v),
-            Ok(None) => Self::Cancelled,
-            Err(e) => Self::Error(e),
+/// Formats the value or the error, or describes why there is neither.
+impl<V: fmt::Display, E: fmt::Display> fmt::Display for WaitResult<V, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitResult::Value(v) => v.fmt(f),
+            WaitResult::Error(e) => write!(f, "error: {}", e),
+            WaitResult::Cancelled => f.write_str("cancelled"),
+            WaitResult::Abandoned => f.write_str("abandoned"),
+            WaitResult::TimedOut => f.write_str("timed out"),
         }
     }
 }
 
-pub struct State<S: Sender> {
-    value: AsyncValue<WaitResult<S::Output>>,
+/// Error for a receiver that was dropped without being completed, see [`WaitResult::Abandoned`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrokenPromise;
+
+impl fmt::Display for BrokenPromise {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("sender dropped its receiver without completing it")
+    }
 }
 
-unsafe impl<S: Sender> Sync for State<S> {}
+impl core::error::Error for BrokenPromise {}
 
-impl<S: Sender> State<S> {
-    pub(crate) fn new() -> Arc<Self> {
-        Arc::new(Self {
-            value: AsyncValue::new(),
-        })
+/// The error of a [`WaitResult`] converted into a `Result`.
+#[derive(Debug)]
+pub enum WaitError<E = crate::Error> {
+    /// The sender completed with an error.
+    Error(E),
+    Cancelled,
+    Abandoned,
+    TimedOut,
+}
+
+impl<E> WaitError<E> {
+    /// Returns the error sent by the sender, if any.
+    pub fn into_error(self) -> Option<E> {
+        match self {
+            WaitError::Error(e) => Some(e),
+            _ => None,
+        }
     }
 
-    pub(crate) fn wait_result(self: Arc<Self>) -> WaitResult<S::Output> {
-        self.value.take()
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, WaitError::Cancelled)
     }
+}
 
-    fn set_result(self: Arc<Self>, result: WaitResult<S::Output>) {
-        self.value.set(result);
+impl<E: fmt::Display> fmt::Display for WaitError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitError::Error(e) => e.fmt(f),
+            WaitError::Cancelled => f.write_str("operation was cancelled"),
+            WaitError::Abandoned => {
+                f.write_str("sender dropped its receiver without completing it")
+            }
+            WaitError::TimedOut => f.write_str("timed out waiting for the sender"),
+        }
     }
 }
 
-pub struct Recv<S: Sender> {
-    state: Arc<State<S>>,
+impl<E: fmt::Debug + fmt::Display> core::error::Error for WaitError<E> {}
+
+/// Converts a value into `Ok` and everything else into the matching [`WaitError`].
+///
+/// ```
+/// use txrx::consumers::sync_wait::{WaitError, WaitResult};
+///
+/// let value: Result<i32, WaitError<&str>> = WaitResult::Value(1).into();
+/// assert_eq!(value.unwrap(), 1);
+///
+/// let cancelled: Result<i32, WaitError<&str>> = WaitResult::Cancelled.into();
+/// assert!(cancelled.unwrap_err().is_cancelled());
+///
+/// let error = Result::from(WaitResult::<i32, _>::Error("failed")).unwrap_err();
+/// assert_eq!(error.into_error(), Some("failed"));
+/// ```
+impl<V, E> From<WaitResult<V, E>> for Result<V, WaitError<E>> {
+    fn from(result: WaitResult<V, E>) -> Self {
+        match result {
+            WaitResult::Value(v) => Ok(v),
+            WaitResult::Error(e) => Err(WaitError::Error(e)),
+            WaitResult::Cancelled => Err(WaitError::Cancelled),
+            WaitResult::Abandoned => Err(WaitError::Abandoned),
+            WaitResult::TimedOut => Err(WaitError::TimedOut),
+        }
+    }
 }
 
-impl<S: Sender> Recv<S> {
-    fn new(state: Arc<State<S>>) -> Self {
-        Self { state }
+impl<V, E> From<crate::Result<V, E>> for WaitResult<V, E> {
+    fn from(r: crate::Result<V, E>) -> Self {
+        match r {
+            Ok(Some(v)) => Self::Value(v),
+            Ok(None) => Self::Cancelled,
+            Err(e) => Self::Error(e),
+        }
     }
 }
 
-impl<S: Sender> Receiver for Recv<S> {
-    type Input = S::Output;
+#[cfg(feature = "std")]
+mod blocking {
+    use super::WaitResult;
+    use crate::env::Env;
+    use crate::priv_sync::Mutex;
+    use crate::run_loop::RunLoop;
+    use crate::stop_token::{StopSource, StopToken};
+    use crate::traits::{Receiver, Sender};
+    use alloc::sync::Arc;
+    use std::time::Duration;
 
-    fn set_value(self, value: Self::Input) {
-        self.state.set_result(WaitResult::Value(value));
+    pub struct State<S: Sender> {
+        result: Mutex<Option<WaitResult<S::Output, S::Error>>>,
+        run_loop: RunLoop,
+        stop_source: StopSource,
     }
 
-    fn set_error(self, error: crate::Error) {
-        self.state.set_result(WaitResult::Error(error));
+    impl<S: Sender> State<S> {
+        pub(crate) fn new() -> Arc<Self> {
+            Arc::new(Self {
+                result: Mutex::new(None),
+                run_loop: RunLoop::new(),
+                stop_source: StopSource::new(),
+            })
+        }
+
+        // Runs work scheduled on the run loop until the result is set.
+        pub(crate) fn wait_result(self: Arc<Self>) -> WaitResult<S::Output, S::Error> {
+            self.run_loop.run();
+            self.result
+                .lock()
+                .take()
+                .expect("Run loop finished without a result")
+        }
+
+        // Same as `wait_result()`, but gives up after `timeout`.
+        fn wait_result_for(self: Arc<Self>, timeout: Duration) -> WaitResult<S::Output, S::Error> {
+            self.run_loop.run_for(timeout);
+            if let Some(result) = self.result.lock().take() {
+                return result;
+            }
+            self.stop_source.request_stop();
+            // The sender may still complete later, but nothing runs the loop anymore.
+            self.run_loop.shutdown();
+            // The sender may have completed since `run_for()` returned. Cancellation and
+            // abandonment at this point are most likely caused by the stop request and shutdown.
+            match self.result.lock().take() {
+                Some(result @ (WaitResult::Value(_) | WaitResult::Error(_))) => result,
+                _ => WaitResult::TimedOut,
+            }
+        }
+
+        fn set_result(self: Arc<Self>, result: WaitResult<S::Output, S::Error>) {
+            *self.result.lock() = Some(result);
+            self.run_loop.finish();
+        }
+    }
+
+    pub struct Recv<S: Sender> {
+        // Taken when the receiver is completed, reports abandonment if still set on drop.
+        state: Option<Arc<State<S>>>,
+        env: Env,
+    }
+
+    impl<S: Sender> Recv<S> {
+        fn new(state: Arc<State<S>>) -> Self {
+            let env = Env::new().with(state.run_loop.scheduler());
+            Self {
+                state: Some(state),
+                env,
+            }
+        }
+
+        fn set_result(mut self, result: WaitResult<S::Output, S::Error>) {
+            if let Some(state) = self.state.take() {
+                state.set_result(result);
+            }
+        }
     }
 
-    fn set_cancelled(self) {
-        self.state.set_result(WaitResult::Cancelled);
+    impl<S: Sender> Drop for Recv<S> {
+        fn drop(&mut self) {
+            if let Some(state) = self.state.take() {
+                state.set_result(WaitResult::Abandoned);
+            }
+        }
+    }
+
+    impl<S: Sender> Receiver for Recv<S> {
+        type Input = S::Output;
+        type Error = S::Error;
+
+        fn set_value(self, value: Self::Input) {
+            self.set_result(WaitResult::Value(value));
+        }
+
+        fn set_error(self, error: Self::Error) {
+            self.set_result(WaitResult::Error(error));
+        }
+
+        fn set_cancelled(self) {
+            self.set_result(WaitResult::Cancelled);
+        }
+
+        fn get_stop_token(&self) -> StopToken {
+            self.state
+                .as_ref()
+                .map(|state| state.stop_source.token())
+                .unwrap_or_default()
+        }
+
+        fn get_env(&self) -> Env {
+            self.env.clone()
+        }
+    }
+
+    /// Starts `sender` and blocks the current thread until it completes.
+    ///
+    /// While waiting, the current thread runs the work scheduled on the
+    /// [`RunLoopScheduler`](crate::run_loop::RunLoopScheduler) found in the environment of the
+    /// receiver, see [`run_loop`](crate::run_loop).
+    ///
+    /// Returns [`WaitResult::Abandoned`] if the receiver is dropped without being completed.
+    pub fn sync_wait<S: 'static + Sender>(sender: S) -> WaitResult<S::Output, S::Error> {
+        let state: Arc<State<S>> = State::new();
+        sender.start(Recv::new(Arc::clone(&state)));
+        state.wait_result()
+    }
+
+    /// Same as [`sync_wait()`], but gives up waiting after `timeout`.
+    ///
+    /// Returns [`WaitResult::TimedOut`] if `sender` hasn't completed in time, in which case stop is
+    /// requested on it. Work it schedules on the run loop after that is cancelled.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use txrx::consumers::sync_wait::sync_wait_timeout;
+    ///
+    /// // Never completes unless triggered.
+    /// let (sender, _trigger) = txrx::test::ManualSender::new();
+    /// assert!(sync_wait_timeout(sender, Duration::from_millis(10)).is_timed_out());
+    /// ```
+    pub fn sync_wait_timeout<S: 'static + Sender>(
+        sender: S,
+        timeout: Duration,
+    ) -> WaitResult<S::Output, S::Error> {
+        let state: Arc<State<S>> = State::new();
+        sender.start(Recv::new(Arc::clone(&state)));
+        state.wait_result_for(timeout)
+    }
+
+    /// Same as [`sync_wait()`], but requests stop on `sender` when stop is requested on
+    /// `stop_token`.
+    ///
+    /// This still waits for `sender` to complete, which it usually does by cancelling soon after
+    /// stop is requested.
+    pub fn sync_wait_with_stop<S: 'static + Sender>(
+        sender: S,
+        stop_token: StopToken,
+    ) -> WaitResult<S::Output, S::Error> {
+        let state: Arc<State<S>> = State::new();
+        let _callback = stop_token.on_stop({
+            let stop_source = state.stop_source.clone();
+            move || {
+                stop_source.request_stop();
+            }
+        });
+        sender.start(Recv::new(Arc::clone(&state)));
+        state.wait_result()
     }
 }
 
-pub fn sync_wait<S: 'static + Sender>(sender: S) -> WaitResult<S::Output> {
-    let state: Arc<State<S>> = State::new();
-    sender.start(Recv::new(Arc::clone(&state)));
-    state.wait_result()
+#[cfg(test)]
+mod tests {
+    use super::{sync_wait_timeout, sync_wait_with_stop, BrokenPromise, WaitError, WaitResult};
+    use crate::stop_token::{StopCallback, StopSource};
+    use crate::test::ManualSender;
+    use crate::timer::TimerScheduler;
+    use crate::traits::{Receiver, Sender, TimeScheduler};
+    use crate::{ImmediateScheduler, SenderExt};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[test]
+    fn abandoned() {
+        // The receiver is dropped together with the trigger.
+        let (sender, trigger) = ManualSender::new();
+        let thread = std::thread::spawn(move || drop(trigger));
+        assert!(sender.sync_wait().is_abandoned());
+        thread.join().unwrap();
+
+        // The receiver is dropped while unwinding from a panic in a closure.
+        let (sender, trigger) = ManualSender::new();
+        let sender = sender.map(|_| -> i32 { panic!("failed") }).ensure_started();
+        assert!(std::thread::spawn(move || trigger.trigger())
+            .join()
+            .is_err());
+        assert!(sender.sync_wait().is_abandoned());
+
+        let (sender, trigger) = ManualSender::new();
+        drop(trigger);
+        let error = sender.sync_wait().into_result().unwrap_err();
+        assert!(error.downcast_ref::<BrokenPromise>().is_some());
+    }
+
+    #[test]
+    fn timeout() {
+        let result = sync_wait_timeout(crate::just(1), Duration::from_secs(60));
+        assert_eq!(result.unwrap(), 1);
+
+        let (sender, _trigger) = ManualSender::new();
+        assert!(sync_wait_timeout(sender, Duration::from_millis(10)).is_timed_out());
+    }
+
+    #[test]
+    fn value_after_timeout() {
+        // Completes with a value when stop is requested, after `run_for()` has returned.
+        struct ValueOnStop(Arc<Mutex<Option<StopCallback>>>);
+
+        impl Sender for ValueOnStop {
+            type Output = i32;
+            type Error = crate::Error;
+            type Scheduler = ImmediateScheduler;
+
+            fn start<R>(self, receiver: R)
+            where
+                R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+            {
+                let callback = receiver
+                    .get_stop_token()
+                    .on_stop(move || receiver.set_value(1));
+                *self.0.lock().unwrap() = Some(callback);
+            }
+        }
+
+        let callback = Arc::new(Mutex::new(None));
+        let result = sync_wait_timeout(ValueOnStop(callback.clone()), Duration::from_millis(10));
+        assert_eq!(result.unwrap(), 1);
+    }
+
+    #[test]
+    fn with_stop() {
+        let source = StopSource::new();
+        let sender = TimerScheduler::new().schedule_after(Duration::from_secs(60));
+        let thread = std::thread::spawn({
+            let source = source.clone();
+            move || {
+                std::thread::sleep(Duration::from_millis(10));
+                source.request_stop();
+            }
+        });
+        assert!(sync_wait_with_stop(sender, source.token()).is_cancelled());
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn combinators() {
+        let value: WaitResult<i32, &str> = WaitResult::Value(2);
+        assert_eq!(value.as_ref().map(|x| x * 10).unwrap(), 20);
+        assert_eq!(value.to_string(), "2");
+        assert_eq!(value.ok(), Some(2));
+
+        let error: WaitResult<i32, &str> = WaitResult::Error("failed");
+        assert!(error.as_ref().map_err(|e| e.len()).is_error());
+        assert_eq!(error.to_string(), "error: failed");
+        assert!(matches!(
+            error.into_std_result(),
+            Err(WaitError::Error("failed"))
+        ));
+
+        let cancelled: WaitResult<i32, &str> = WaitResult::Cancelled;
+        let error = cancelled.into_std_result().unwrap_err();
+        assert!(error.is_cancelled());
+        assert_eq!(error.to_string(), "operation was cancelled");
+    }
+
+    #[test]
+    fn question_mark() {
+        fn wait<S>(sender: S) -> Result<i32, crate::Error>
+        where
+            S: 'static + crate::traits::Sender<Output = i32, Error = crate::Error>,
+        {
+            Ok(sender.sync_wait().into_std_result()?)
+        }
+        assert_eq!(wait(crate::just(1)).unwrap(), 1);
+        let error = wait(crate::factories::cancelled().map(|_| 1)).unwrap_err();
+        assert!(error.downcast_ref::<WaitError>().unwrap().is_cancelled());
+    }
 }
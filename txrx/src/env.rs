@@ -0,0 +1,137 @@
+//! Contextual information that receivers expose to the senders they are connected to.
+//!
+//! An [`Env`] is an immutable, type-indexed set of values. Receivers return their environment from
+//! [`Receiver::get_env()`](crate::traits::Receiver::get_env) and adaptors forward the environment
+//! of the receiver they are connected to. This way a consumer at the end of a pipeline can make
+//! runtime context, like a preferred scheduler, a deadline or a tracing span, available to every
+//! sender in the pipeline.
+//!
+//! Values are added with [`with_env()`](crate::SenderExt::with_env) and read with
+//! [`read_env()`](crate::factories::read_env).
+//!
+//! ## Examples
+//!
+//! ```
+//! use txrx::env::Env;
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Deadline(u32);
+//!
+//! let env = Env::new().with(Deadline(10)).with("span");
+//! assert_eq!(env.get::<Deadline>(), Some(&Deadline(10)));
+//! assert_eq!(env.get::<&str>(), Some(&"span"));
+//! assert_eq!(env.get::<u32>(), None);
+//! ```
+use crate::priv_sync::Mutex;
+use crate::traits::Scheduler;
+use crate::AnyScheduler;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::any::Any;
+
+struct Node {
+    value: Box<dyn Any + Send + Sync>,
+    next: Option<Arc<Node>>,
+}
+
+/// A type-indexed set of values. Cloning an `Env` is cheap.
+#[derive(Clone, Default)]
+pub struct Env {
+    head: Option<Arc<Node>>,
+}
+
+impl Env {
+    /// Returns an empty environment.
+    pub fn new() -> Self {
+        Self { head: None }
+    }
+
+    /// Returns a new environment that contains `value` in addition to all values in `self`.
+    ///
+    /// If `self` already contains a value of type `T` it is shadowed by `value`.
+    pub fn with<T: 'static + Send + Sync>(&self, value: T) -> Self {
+        Self {
+            head: Some(Arc::new(Node {
+                value: Box::new(value),
+                next: self.head.clone(),
+            })),
+        }
+    }
+
+    /// Returns the most recently added value of type `T`, if any.
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        let mut current = self.head.as_ref();
+        while let Some(node) = current {
+            if let Some(value) = node.value.downcast_ref::<T>() {
+                return Some(value);
+            }
+            current = node.next.as_ref();
+        }
+        None
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.get::<T>().is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+}
+
+/// A scheduler that senders without a scheduler of their own can run work on.
+///
+/// [`bulk()`](crate::SenderExt::bulk) runs its functions on it when its input sender has no
+/// completion scheduler.
+///
+/// ## Examples
+///
+/// ```
+/// use txrx::env::PreferredScheduler;
+/// use txrx::manual_executor::ManualExecutor;
+/// use txrx::test::ManualSender;
+/// use txrx::SenderExt;
+///
+/// let executor = ManualExecutor::new();
+/// let (sender, trigger) = ManualSender::new();
+/// let sender = sender
+///     .bulk(2, |step, _| step)
+///     .with_env(PreferredScheduler::new(executor.scheduler()))
+///     .ensure_started();
+/// trigger.trigger();
+/// // The first function is queued on the executor.
+/// assert!(!sender.is_complete());
+/// executor.runner().run_one();
+/// assert_eq!(sender.sync_wait().unwrap(), ((), vec![0, 1]));
+/// ```
+pub struct PreferredScheduler {
+    // Schedulers don't have to be `Sync`, values in an environment do.
+    scheduler: Mutex<AnyScheduler>,
+}
+
+impl PreferredScheduler {
+    pub fn new<S: Scheduler>(scheduler: S) -> Self {
+        Self {
+            scheduler: Mutex::new(AnyScheduler::new(scheduler)),
+        }
+    }
+
+    pub fn get(&self) -> AnyScheduler {
+        self.scheduler.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Env;
+
+    #[test]
+    fn shadowing() {
+        let env = Env::new().with(1u32);
+        let shadowed = env.with(2u32);
+        assert_eq!(env.get::<u32>(), Some(&1));
+        assert_eq!(shadowed.get::<u32>(), Some(&2));
+        assert!(!shadowed.contains::<u64>());
+        assert!(Env::new().is_empty());
+    }
+}
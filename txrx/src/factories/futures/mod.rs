@@ -1,5 +1,5 @@
-use crate::traits::{Receiver, Scheduler, Sender};
-use std::future::Future;
+use crate::traits::{Channel, Receiver, Scheduler, Sender};
+use core::future::Future;
 
 mod waker;
 
@@ -16,17 +16,18 @@ where
     Sched: Scheduler,
 {
     type Output = Fut::Output;
+    type Error = crate::Error;
     type Scheduler = Sched;
 
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         waker::WakerData::new(self.future, self.scheduler, receiver).start();
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        self.scheduler.clone()
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(self.scheduler.clone())
     }
 }
 
@@ -42,3 +43,38 @@ where
 {
     FutureSender { future, scheduler }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::manual_executor::ManualExecutor;
+    use crate::test::Stoppable;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+
+    #[test]
+    fn stop_cancels_pending_future() {
+        // Stays pending, but keeps its waker alive like a real future waiting for an event.
+        struct Waiting(Arc<Mutex<Option<Waker>>>);
+
+        impl Future for Waiting {
+            type Output = ();
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+                *self.0.lock().unwrap() = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+
+        let exec = ManualExecutor::new();
+        let waker = Arc::new(Mutex::new(None));
+        let started =
+            Stoppable::start(super::from_future(exec.scheduler(), Waiting(waker.clone())));
+        exec.runner().run_until_idle();
+        assert!(started.take().is_none());
+        started.request_stop();
+        exec.runner().run_until_idle();
+        assert!(started.take().unwrap().is_cancelled());
+    }
+}
@@ -1,10 +1,13 @@
+use crate::priv_sync::Mutex;
+use crate::stop_token::StopCallback;
 use crate::traits::{Receiver, Scheduler};
-use std::cell::UnsafeCell;
-use std::future::Future;
-use std::pin::Pin;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Weak};
-use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use alloc::boxed::Box;
+use alloc::sync::{Arc, Weak};
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
 
 pub trait Wakable {
     fn wakeup(&self);
@@ -16,6 +19,7 @@ pub struct WakerData<F: Future, Sched: Scheduler, Recv> {
     scheduler: Sched,
     state: AtomicUsize,
     weak_self: UnsafeCell<Weak<Self>>,
+    stop_callback: Mutex<Option<StopCallback>>,
 }
 
 unsafe impl<F: Send + Future, Sched: Scheduler, Recv: Send> Sync for WakerData<F, Sched, Recv> {}
@@ -39,12 +43,28 @@ where
             state: AtomicUsize::new(0),
             receiver: UnsafeCell::new(Some(receiver)),
             weak_self: UnsafeCell::new(Weak::new()),
+            stop_callback: Mutex::new(None),
         });
         *unsafe { &mut *ret.weak_self.get() } = Arc::downgrade(&ret);
         ret
     }
 
     pub fn start(self: Arc<Self>) {
+        // Safety: the receiver is only ever accessed by poll, which hasn't been scheduled yet.
+        let stop_token = unsafe { &*self.receiver.get() }
+            .as_ref()
+            .map(|r| r.get_stop_token())
+            .unwrap_or_default();
+        if stop_token.stop_possible() {
+            // Wake up on stop so the receiver is cancelled even if the future never wakes again.
+            let weak = Arc::downgrade(&self);
+            let callback = stop_token.on_stop(move || {
+                if let Some(this) = weak.upgrade() {
+                    this.wakeup_impl();
+                }
+            });
+            *self.stop_callback.lock() = Some(callback);
+        }
         self.wakeup_impl();
     }
 
@@ -86,10 +106,30 @@ where
         }
     }
 
+    fn set_receiver_cancelled(&self) {
+        let receiver = unsafe { &mut *self.receiver.get() };
+        if let Some(receiver) = receiver.take() {
+            receiver.set_cancelled()
+        }
+    }
+
+    fn stop_requested(&self) -> bool {
+        let receiver = unsafe { &*self.receiver.get() };
+        receiver
+            .as_ref()
+            .is_some_and(|r| r.get_stop_token().stop_requested())
+    }
+
     fn poll(self: Arc<Self>) {
         if self.is_finished() {
             return;
         }
+        if self.stop_requested() {
+            self.mark_finished();
+            self.stop_callback.lock().take();
+            self.set_receiver_cancelled();
+            return;
+        }
         let waker = new_waker(self.clone());
         let mut context = Context::from_waker(&waker);
 
@@ -101,6 +141,7 @@ where
         match future.poll(&mut context) {
             Poll::Ready(value) => {
                 self.mark_finished();
+                self.stop_callback.lock().take();
                 self.set_receiver_value(value);
             }
             Poll::Pending => {
@@ -152,7 +193,7 @@ unsafe fn clone_waker_impl(data: *const ()) -> RawWaker {
     let me = data_from_waker_data(data);
     let new = me.clone();
     // preserve strong count
-    Box::into_raw(me);
+    let _ = Box::into_raw(me);
 
     RawWaker::new(Box::into_raw(new) as *const (), waker_vtable())
 }
@@ -166,7 +207,7 @@ unsafe fn wake_by_ref_impl(data: *const ()) {
     let me = data_from_waker_data(data);
     me.wakeup();
     // Preserve ref count
-    Box::into_raw(me);
+    let _ = Box::into_raw(me);
 }
 
 unsafe fn drop_impl(data: *const ()) {
@@ -5,37 +5,31 @@ pub struct CancelledSender;
 
 impl Sender for CancelledSender {
     type Output = ();
+    type Error = crate::Error;
     type Scheduler = ImmediateScheduler;
 
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         receiver.set_cancelled();
     }
-
-    fn get_scheduler(&self) -> Self::Scheduler {
-        ImmediateScheduler
-    }
 }
 
 pub struct ErrorSender<E>(pub E);
 
 impl<E> Sender for ErrorSender<E>
 where
-    E: 'static + Send + Sync + std::error::Error,
+    E: 'static + Send,
 {
     type Output = ();
+    type Error = E;
     type Scheduler = ImmediateScheduler;
 
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
-        receiver.set_error(Box::new(self.0));
-    }
-
-    fn get_scheduler(&self) -> Self::Scheduler {
-        ImmediateScheduler
+        receiver.set_error(self.0);
     }
 }
@@ -1,5 +1,6 @@
-use crate::traits::{Receiver, Sender};
+use crate::traits::{Connect, OperationState, Receiver, Sender};
 use crate::ImmediateScheduler;
+use core::pin::Pin;
 
 pub struct Just<T> {
     data: T,
@@ -16,16 +17,47 @@ where
     T: 'static + Send,
 {
     type Output = T;
+    type Error = crate::Error;
     type Scheduler = ImmediateScheduler;
 
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         receiver.set_value(self.data);
     }
+}
+
+impl<T, R> Connect<R> for Just<T>
+where
+    R: Receiver<Input = T>,
+{
+    type Operation = JustOperation<T, R>;
+
+    #[inline]
+    fn connect(self, receiver: R) -> Self::Operation {
+        JustOperation {
+            data_and_receiver: Some((self.data, receiver)),
+        }
+    }
+}
+
+/// Operation state of [`Just`].
+pub struct JustOperation<T, R> {
+    data_and_receiver: Option<(T, R)>,
+}
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        ImmediateScheduler
+// Neither the value nor the receiver is pinned, they are moved out when started.
+impl<T, R> Unpin for JustOperation<T, R> {}
+
+impl<T, R> OperationState for JustOperation<T, R>
+where
+    R: Receiver<Input = T>,
+{
+    #[inline]
+    unsafe fn start(self: Pin<&mut Self>) {
+        if let Some((data, receiver)) = self.get_mut().data_and_receiver.take() {
+            receiver.set_value(data);
+        }
     }
 }
@@ -2,6 +2,7 @@ pub mod futures;
 pub mod immediate;
 pub mod just_sender;
 pub mod on_scheduler;
+pub mod read_env;
 
 pub use futures::from_future;
 
@@ -40,8 +41,33 @@ pub fn cancelled() -> immediate::CancelledSender {
     immediate::CancelledSender
 }
 
+/// Create a sender that sends the value of type `T` found in the environment of the receiver it
+/// is started with, or `None` if the environment doesn't contain a `T`.
+///
+/// See [`env`](crate::env) for details on environments.
+///
+/// ## Examples
+///
+/// ```
+/// use txrx::factories::read_env;
+/// use txrx::SenderExt;
+///
+/// let value = read_env::<&str>()
+///     .map(|greeting| greeting.unwrap_or("goodbye"))
+///     .with_env("hello")
+///     .sync_wait()
+///     .unwrap();
+/// assert_eq!(value, "hello");
+/// ```
+pub fn read_env<T: 'static + Send + Clone>() -> read_env::ReadEnv<T> {
+    read_env::ReadEnv::new()
+}
+
 /// A sender that only sends the `error` signal.
 ///
+/// The error type of the sender is `E`, use [`map_error()`](crate::SenderExt::map_error) to
+/// convert it into another error type, for instance [`txrx::Error`](crate::Error).
+///
 /// ## Examples
 ///
 /// ```
@@ -63,8 +89,14 @@ pub fn cancelled() -> immediate::CancelledSender {
 /// impl Error for MyError {}
 ///
 /// let result = error(MyError::MyCustomError).sync_wait().unwrap_error();
-/// assert_eq!(*result.downcast::<MyError>().unwrap(), MyError::MyCustomError);
+/// assert_eq!(result, MyError::MyCustomError);
+///
+/// let boxed: txrx::Error = error(MyError::MyCustomError)
+///     .map_error(Into::into)
+///     .sync_wait()
+///     .unwrap_error();
+/// assert_eq!(*boxed.downcast::<MyError>().unwrap(), MyError::MyCustomError);
 /// ```
-pub fn error<E: 'static + Send + Sync + std::error::Error>(error: E) -> immediate::ErrorSender<E> {
+pub fn error<E: 'static + Send>(error: E) -> immediate::ErrorSender<E> {
     immediate::ErrorSender(error)
 }
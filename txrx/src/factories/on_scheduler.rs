@@ -1,4 +1,4 @@
-use crate::traits::{Receiver, Scheduler, Sender, Work};
+use crate::traits::{Channel, Receiver, Scheduler, Sender, Work};
 
 pub struct On<SchedulerT, SenderT> {
     scheduler: SchedulerT,
@@ -17,11 +17,12 @@ where
     SenderT: 'static + Send + Sender,
 {
     type Output = SenderT::Output;
+    type Error = SenderT::Error;
     type Scheduler = SchedulerT;
 
     fn start<R>(mut self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         self.scheduler.execute(OnWork {
             sender: self.sender,
@@ -29,8 +30,8 @@ where
         });
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        self.scheduler.clone()
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(self.scheduler.clone())
     }
 }
 
@@ -42,11 +43,15 @@ pub struct OnWork<SenderT, ReceiverT> {
 impl<SenderT, ReceiverT> Work for OnWork<SenderT, ReceiverT>
 where
     SenderT: Sender,
-    ReceiverT: 'static + Send + Receiver<Input = SenderT::Output>,
+    ReceiverT: 'static + Send + Receiver<Input = SenderT::Output, Error = SenderT::Error>,
 {
     fn execute(self) {
         self.sender.start(self.receiver);
     }
+
+    fn cancel(self) {
+        self.receiver.set_cancelled();
+    }
 }
 
 pub fn on<Scheduler, Sender>(scheduler: Scheduler, sender: Sender) -> On<Scheduler, Sender> {
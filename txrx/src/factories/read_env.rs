@@ -0,0 +1,44 @@
+use crate::traits::{Receiver, Sender};
+use crate::utility::PanicGuard;
+use crate::ImmediateScheduler;
+use core::marker::PhantomData;
+
+/// Sender that sends a value from the environment of the receiver.
+/// See [`read_env()`](crate::factories::read_env) for details.
+pub struct ReadEnv<T> {
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> ReadEnv<T> {
+    pub fn new() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for ReadEnv<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Sender for ReadEnv<T>
+where
+    T: 'static + Send + Clone,
+{
+    type Output = Option<T>;
+    type Error = crate::Error;
+    type Scheduler = ImmediateScheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        // `T::clone()` is the only user code that runs here.
+        let env = receiver.get_env();
+        if let Some(value) = PanicGuard::new(&env).call(|| env.get::<T>().cloned()) {
+            receiver.set_value(value);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::traits::{Receiver, Scheduler, Sender, Work};
+use crate::traits::{Channel, Receiver, Scheduler, Sender, Work};
 
 #[derive(Copy, Clone)]
 pub struct ImmediateScheduler;
@@ -20,16 +20,21 @@ impl Scheduler for ImmediateScheduler {
 
 impl Sender for ImmediateScheduler {
     type Output = ();
+    type Error = crate::Error;
     type Scheduler = Self;
 
     fn start<R>(self, receiver: R)
     where
-        R: Receiver<Input = Self::Output>,
+        R: Receiver<Input = Self::Output, Error = Self::Error>,
     {
-        receiver.set_value(());
+        if receiver.get_stop_token().stop_requested() {
+            receiver.set_cancelled();
+        } else {
+            receiver.set_value(());
+        }
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        Self
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(Self)
     }
 }
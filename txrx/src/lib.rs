@@ -1,26 +1,83 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+//! A Sender/Receiver implementation inspired by the C++ [P2300] proposal.
+//!
+//! Work is described by [senders](traits::Sender) that are composed with the adaptors in
+//! [`SenderExt`], and run on [schedulers](traits::Scheduler). Nothing happens until a sender is
+//! consumed, for instance with [`sync_wait()`] or [`start_detached()`].
+//!
+//! ```
+//! use txrx::manual_executor::ManualExecutor;
+//! use txrx::traits::Scheduler;
+//! use txrx::SenderExt;
+//!
+//! let executor = ManualExecutor::new();
+//! let runner = executor.runner();
+//! std::thread::spawn(move || runner.run());
+//!
+//! let result = executor
+//!     .scheduler()
+//!     .schedule()
+//!     .map(|_| 13)
+//!     .when_both(txrx::just(29))
+//!     .map(|(a, b)| a + b)
+//!     .sync_wait();
+//! assert_eq!(result.unwrap(), 42);
+//! ```
+//!
+//! ## `no_std`
+//!
+//! Without the default `std` feature only `alloc` is required. Everything that blocks the calling
+//! thread or needs a clock, `sync_wait()`, `manual_executor`, `run_loop`, `scope()` and `timer`,
+//! needs `std`.
+//!
+//! [P2300]: http://www.open-std.org/jtc1/sc22/wg21/docs/papers/2021/p2300r1.html
+
+extern crate alloc;
+
 pub mod adaptors;
 pub mod consumers;
+pub mod env;
 pub mod factories;
+pub mod local;
+#[cfg(feature = "std")]
 pub mod manual_executor;
+#[cfg(feature = "std")]
+pub mod run_loop;
+#[cfg(feature = "std")]
+pub mod scope;
+pub mod stop_token;
+#[cfg(feature = "std")]
+pub mod timer;
 pub mod traits;
 pub mod utility;
 
 pub(crate) mod priv_sync;
 
+mod any_scheduler;
+mod any_sender;
 mod immediate_scheduler;
 
 pub mod test;
 
+pub use adaptors::when_all::{when_all, when_all_iter};
+pub use adaptors::when_any::when_any_iter;
+pub use consumers::async_scope::AsyncScope;
 pub use consumers::start_detached::start_detached;
+#[cfg(feature = "std")]
 pub use consumers::sync_wait::sync_wait;
 pub use factories::from_future;
 pub use factories::just;
+#[cfg(feature = "std")]
+pub use scope::scope;
 
+pub use any_scheduler::{AnyScheduleSender, AnyScheduler};
+pub use any_sender::AnySender;
 pub use immediate_scheduler::ImmediateScheduler;
 pub use traits::SenderExt;
 
-pub type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
-pub type Result<T> = std::result::Result<Option<T>, crate::Error>;
+/// The default error type used by senders.
+pub type Error = alloc::boxed::Box<dyn core::error::Error + Send + Sync + 'static>;
+pub type Result<T, E = crate::Error> = core::result::Result<Option<T>, E>;
 
 #[cfg(test)]
 mod tests {
@@ -44,6 +101,52 @@ mod tests {
         assert!(res.is_cancelled());
     }
 
+    #[test]
+    fn recover() {
+        let res = crate::factories::cancelled()
+            .map(|_| 1)
+            .upon_cancelled(|| 2)
+            .upon_error(|_| 3)
+            .sync_wait();
+        assert_eq!(res.unwrap(), 2);
+
+        let res = crate::factories::error(10)
+            .map(|_: ()| 1)
+            .let_error(|x| crate::factories::just(x * 2).map_error(|_| 0))
+            .sync_wait();
+        assert_eq!(res.unwrap(), 20);
+
+        let res = crate::factories::cancelled()
+            .map(|_| 1)
+            .let_cancelled(|| crate::factories::error("failed").map(|_: ()| 0))
+            .sync_wait();
+        assert_eq!(res.unwrap_error().to_string(), "failed");
+    }
+
+    #[test]
+    fn typed_errors() {
+        #[derive(Debug, PartialEq)]
+        struct Small(i32);
+        #[derive(Debug, PartialEq)]
+        struct Large(i64);
+        impl From<Small> for Large {
+            fn from(x: Small) -> Self {
+                Large(x.0 as i64)
+            }
+        }
+
+        let res = crate::factories::error(Large(1))
+            .when_both(crate::factories::error(Small(2)))
+            .sync_wait();
+        assert_eq!(res.unwrap_error(), Large(1));
+
+        let res = crate::factories::just(())
+            .map_error(|e: crate::Error| Large(e.to_string().len() as i64))
+            .and_then(|_| crate::factories::error(Small(3)))
+            .sync_wait();
+        assert_eq!(res.unwrap_error(), Large(3));
+    }
+
     #[test]
     fn manual_executor_test() {
         let exec = Arc::new(crate::manual_executor::ManualExecutor::new());
@@ -0,0 +1,89 @@
+use crate::adaptors::map_error;
+use crate::env::Env;
+use crate::stop_token::StopToken;
+use crate::traits::{LocalSender, Receiver};
+use core::marker::PhantomData;
+
+/// Local version of [`AndThen`](crate::adaptors::and_then::AndThen).
+pub struct AndThen<Input, Func> {
+    input: Input,
+    func: Func,
+}
+
+impl<Input, Func> AndThen<Input, Func> {
+    pub fn new(input: Input, func: Func) -> Self {
+        Self { input, func }
+    }
+}
+
+impl<NextSender, Input, Func> LocalSender for AndThen<Input, Func>
+where
+    Input: LocalSender,
+    Func: 'static + FnOnce(Input::Output) -> NextSender,
+    NextSender: LocalSender,
+    NextSender::Error: Into<Input::Error>,
+{
+    type Output = NextSender::Output;
+    type Error = Input::Error;
+    type Scheduler = Input::Scheduler;
+
+    #[inline]
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        self.input.start(AndThenReceiver::new(self.func, receiver));
+    }
+}
+
+pub struct AndThenReceiver<Input, Func, NextReceiver> {
+    next: NextReceiver,
+    func: Func,
+    _ph: PhantomData<Input>,
+}
+
+impl<Input, Func, NextReceiver> AndThenReceiver<Input, Func, NextReceiver> {
+    fn new(func: Func, next: NextReceiver) -> Self {
+        Self {
+            next,
+            func,
+            _ph: PhantomData,
+        }
+    }
+}
+
+impl<Input, Func, NextReceiver, Ret> Receiver for AndThenReceiver<Input, Func, NextReceiver>
+where
+    Func: FnOnce(Input) -> Ret,
+    Ret: LocalSender,
+    Ret::Error: Into<NextReceiver::Error>,
+    NextReceiver: 'static + Receiver<Input = Ret::Output>,
+{
+    type Input = Input;
+    type Error = NextReceiver::Error;
+
+    #[inline]
+    fn set_value(self, value: Self::Input) {
+        (self.func)(value).start(map_error::Receiver::new(self.next, Into::into));
+    }
+
+    #[inline]
+    fn set_error(self, error: Self::Error) {
+        self.next.set_error(error);
+    }
+
+    #[inline]
+    fn set_cancelled(self) {
+        self.next.set_cancelled();
+    }
+
+    #[inline]
+    fn get_stop_token(&self) -> StopToken {
+        self.next.get_stop_token()
+    }
+
+    #[inline]
+    fn get_env(&self) -> Env {
+        self.next.get_env()
+    }
+}
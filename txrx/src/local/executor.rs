@@ -0,0 +1,254 @@
+use crate::consumers::sync_wait::WaitResult;
+use crate::traits::{Channel, LocalSender, Receiver, Work};
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+type QueueType = VecDeque<Box<dyn FnOnce()>>;
+
+struct Inner {
+    queue: RefCell<QueueType>,
+}
+
+impl Inner {
+    fn add<F: 'static + FnOnce()>(&self, work: F) {
+        self.queue.borrow_mut().push_back(Box::new(work));
+    }
+
+    fn run_one(&self) -> bool {
+        // The borrow is released before running, the work is free to schedule more work.
+        let to_run = self.queue.borrow_mut().pop_front();
+        if let Some(to_run) = to_run {
+            to_run();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// A run loop for [`LocalSender`]s, driven by the thread that owns it.
+///
+/// Unlike [`ManualExecutor`](crate::manual_executor::ManualExecutor) this never blocks; when the
+/// queue is empty there is nothing that could add more work to it.
+pub struct LocalExecutor {
+    inner: Rc<Inner>,
+}
+
+impl Default for LocalExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocalExecutor {
+    pub fn new() -> Self {
+        Self {
+            inner: Rc::new(Inner {
+                queue: RefCell::new(QueueType::new()),
+            }),
+        }
+    }
+
+    pub fn scheduler(&self) -> Scheduler {
+        Scheduler {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Runs the next queued item, returns `false` if the queue was empty.
+    pub fn run_one(&self) -> bool {
+        self.inner.run_one()
+    }
+
+    /// Runs queued items until the queue is empty and returns the number of items run.
+    pub fn run_until_idle(&self) -> usize {
+        let mut count = 0;
+        while self.run_one() {
+            count += 1;
+        }
+        count
+    }
+
+    /// Starts `sender` and runs queued items until it completes.
+    ///
+    /// Returns [`WaitResult::Abandoned`] if the receiver is dropped without being completed.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the queue runs empty before `sender` completes, since nothing can complete it at
+    /// that point.
+    pub fn sync_wait<S>(&self, sender: S) -> WaitResult<S::Output, S::Error>
+    where
+        S: LocalSender,
+    {
+        let result = Rc::new(RefCell::new(None));
+        sender.start(WaitReceiver {
+            result: Some(result.clone()),
+        });
+        loop {
+            if let Some(result) = result.borrow_mut().take() {
+                return result;
+            }
+            if !self.run_one() {
+                panic!("LocalExecutor ran out of work before the sender completed");
+            }
+        }
+    }
+}
+
+type ResultSlot<T, E> = Rc<RefCell<Option<WaitResult<T, E>>>>;
+
+struct WaitReceiver<T, E> {
+    // Taken when the receiver is completed, reports abandonment if still set on drop.
+    result: Option<ResultSlot<T, E>>,
+}
+
+impl<T, E> WaitReceiver<T, E> {
+    fn set_result(mut self, result: WaitResult<T, E>) {
+        if let Some(slot) = self.result.take() {
+            *slot.borrow_mut() = Some(result);
+        }
+    }
+}
+
+impl<T, E> Drop for WaitReceiver<T, E> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.result.take() {
+            *slot.borrow_mut() = Some(WaitResult::Abandoned);
+        }
+    }
+}
+
+impl<T, E> Receiver for WaitReceiver<T, E> {
+    type Input = T;
+    type Error = E;
+
+    fn set_value(self, value: Self::Input) {
+        self.set_result(WaitResult::Value(value));
+    }
+
+    fn set_error(self, error: Self::Error) {
+        self.set_result(WaitResult::Error(error));
+    }
+
+    fn set_cancelled(self) {
+        self.set_result(WaitResult::Cancelled);
+    }
+}
+
+pub struct ScheduledSender {
+    inner: Rc<Inner>,
+}
+
+impl LocalSender for ScheduledSender {
+    type Output = ();
+    type Error = crate::Error;
+    type Scheduler = Scheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        self.inner.add(move || {
+            if receiver.get_stop_token().stop_requested() {
+                receiver.set_cancelled();
+            } else {
+                receiver.set_value(());
+            }
+        });
+    }
+
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(Self::Scheduler {
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct Scheduler {
+    inner: Rc<Inner>,
+}
+
+impl crate::traits::LocalScheduler for Scheduler {
+    type Sender = ScheduledSender;
+
+    fn schedule(&mut self) -> Self::Sender {
+        ScheduledSender {
+            inner: self.inner.clone(),
+        }
+    }
+
+    fn execute<W>(&mut self, work: W)
+    where
+        W: 'static + Work,
+    {
+        self.inner.add(move || {
+            work.execute();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalExecutor;
+    use crate::local::just;
+    use crate::traits::{LocalScheduler, LocalSender, LocalSenderExt, Receiver};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn and_then() {
+        let executor = LocalExecutor::new();
+        let scheduler = executor.scheduler();
+        let count = Rc::new(Cell::new(0));
+        let sender = just(count.clone()).and_then(move |count| {
+            let mut scheduler = scheduler.clone();
+            scheduler.schedule().map(move |_| {
+                count.set(count.get() + 1);
+                count
+            })
+        });
+        let result = executor.sync_wait(sender).unwrap();
+        assert_eq!(result.get(), 1);
+        assert_eq!(count.get(), 1);
+        assert_eq!(executor.run_until_idle(), 0);
+    }
+
+    struct Fail;
+
+    impl LocalSender for Fail {
+        type Output = Rc<()>;
+        type Error = crate::Error;
+        type Scheduler = super::Scheduler;
+
+        fn start<R>(self, receiver: R)
+        where
+            R: 'static + Receiver<Input = Self::Output, Error = Self::Error>,
+        {
+            receiver.set_error("failed".into());
+        }
+    }
+
+    #[test]
+    fn when_both_error() {
+        let executor = LocalExecutor::new();
+        let reached = Rc::new(Cell::new(false));
+        let sender = executor
+            .scheduler()
+            .schedule()
+            .map({
+                let reached = reached.clone();
+                move |_| reached.set(true)
+            })
+            .when_both(Fail);
+        let result = executor.sync_wait(sender);
+        assert_eq!(result.unwrap_error().to_string(), "failed");
+
+        // The error requested stop, so the scheduled work is cancelled.
+        assert_eq!(executor.run_until_idle(), 1);
+        assert!(!reached.get());
+    }
+}
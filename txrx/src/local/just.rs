@@ -0,0 +1,26 @@
+use crate::local::executor::Scheduler;
+use crate::traits::{LocalSender, Receiver};
+
+/// Local sender that sends a value that doesn't have to be `Send`.
+pub struct Just<T> {
+    data: T,
+}
+
+impl<T> Just<T> {
+    pub fn new(value: T) -> Self {
+        Self { data: value }
+    }
+}
+
+impl<T: 'static> LocalSender for Just<T> {
+    type Output = T;
+    type Error = crate::Error;
+    type Scheduler = Scheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        receiver.set_value(self.data);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::adaptors::map::Receiver;
+use crate::traits::{Channel, LocalSender, Receiver as ReceiverT};
+
+/// Local version of [`Map`](crate::adaptors::map::Map).
+pub struct Map<S, F> {
+    sender: S,
+    func: F,
+}
+
+impl<S, F> Map<S, F> {
+    pub fn new(sender: S, func: F) -> Self {
+        Self { sender, func }
+    }
+}
+
+impl<Src, Func, Ret> LocalSender for Map<Src, Func>
+where
+    Src: LocalSender,
+    Func: 'static + FnOnce(Src::Output) -> Ret,
+    Ret: 'static,
+{
+    type Output = Ret;
+    type Error = Src::Error;
+    type Scheduler = Src::Scheduler;
+
+    #[inline]
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + ReceiverT<Input = Self::Output, Error = Self::Error>,
+    {
+        self.sender.start(Receiver::new(receiver, self.func));
+    }
+
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        self.sender.get_completion_scheduler(channel)
+    }
+}
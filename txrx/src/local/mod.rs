@@ -0,0 +1,44 @@
+//! Senders and schedulers for work that never leaves the current thread.
+//!
+//! [`Sender`](crate::traits::Sender) requires both senders and receivers to be `Send`, which makes
+//! it impossible to build pipelines that capture `Rc`, `RefCell` or other thread-bound data. The
+//! [`LocalSender`](crate::traits::LocalSender) and [`LocalScheduler`](crate::traits::LocalScheduler)
+//! traits drop that requirement, and the [`LocalExecutor`] runs local work on the thread that
+//! drives it.
+//!
+//! ## Examples
+//!
+//! ```
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//! use txrx::local::LocalExecutor;
+//! use txrx::traits::{LocalScheduler, LocalSenderExt};
+//!
+//! let executor = LocalExecutor::new();
+//! let log = Rc::new(RefCell::new(Vec::new()));
+//!
+//! let sender = executor.scheduler().schedule().map({
+//!     let log = log.clone();
+//!     move |_| {
+//!         log.borrow_mut().push("scheduled");
+//!         Rc::new(10)
+//!     }
+//! })
+//! .when_both(txrx::local::just(Rc::new("hello")));
+//!
+//! let (value, greeting) = executor.sync_wait(sender).unwrap();
+//! assert_eq!((*value, *greeting), (10, "hello"));
+//! assert_eq!(*log.borrow(), vec!["scheduled"]);
+//! ```
+pub use executor::LocalExecutor;
+
+pub mod and_then;
+pub mod executor;
+pub mod just;
+pub mod map;
+pub mod when_both;
+
+/// Create a local sender that, when started, immediately sends its value to the receiver.
+pub fn just<T: 'static>(value: T) -> just::Just<T> {
+    just::Just::new(value)
+}
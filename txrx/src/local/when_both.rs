@@ -0,0 +1,194 @@
+use crate::env::Env;
+use crate::stop_token::{StopCallback, StopSource, StopToken};
+use crate::traits::{LocalSender, Receiver};
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::marker::PhantomData;
+
+/// Local version of [`WhenBoth`](crate::adaptors::when_both::WhenBoth).
+///
+/// Both senders complete on the current thread, so the receiver is completed inline by whichever
+/// sender completes last.
+pub struct WhenBoth<Left, Right> {
+    left: Left,
+    right: Right,
+}
+
+impl<Left, Right> WhenBoth<Left, Right> {
+    pub fn new(left: Left, right: Right) -> Self {
+        Self { left, right }
+    }
+}
+
+impl<Left, Right> LocalSender for WhenBoth<Left, Right>
+where
+    Left: LocalSender,
+    Right: LocalSender,
+    Right::Error: Into<Left::Error>,
+{
+    type Output = (Left::Output, Right::Output);
+    type Error = Left::Error;
+    type Scheduler = Left::Scheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        let state = Rc::new(SharedState::new(receiver));
+        self.left.start(LeftReceiver {
+            state: state.clone(),
+        });
+        self.right.start(RightReceiver {
+            state,
+            _phantom: PhantomData,
+        });
+    }
+}
+
+struct Data<A, B, Next> {
+    left: Option<A>,
+    right: Option<B>,
+    next: Option<Next>,
+    // Forwards stop requests from `next` to both senders, dropped once `next` is completed.
+    stop_callback: Option<StopCallback>,
+}
+
+struct SharedState<A, B, Next> {
+    data: RefCell<Data<A, B, Next>>,
+    stop_source: StopSource,
+    env: Env,
+}
+
+impl<A, B, Next: Receiver<Input = (A, B)>> SharedState<A, B, Next> {
+    fn new(next: Next) -> Self {
+        let stop_source = StopSource::new();
+        let stop_callback = next.get_stop_token().on_stop({
+            let stop_source = stop_source.clone();
+            move || {
+                stop_source.request_stop();
+            }
+        });
+        let env = next.get_env();
+        Self {
+            data: RefCell::new(Data {
+                left: None,
+                right: None,
+                next: Some(next),
+                stop_callback: Some(stop_callback),
+            }),
+            stop_source,
+            env,
+        }
+    }
+
+    fn take_next(&self) -> Option<Next> {
+        let (next, stop_callback) = {
+            let mut data = self.data.borrow_mut();
+            (data.next.take(), data.stop_callback.take())
+        };
+        drop(stop_callback);
+        next
+    }
+
+    fn set_value(&self, left: Option<A>, right: Option<B>) {
+        let values = {
+            let mut data = self.data.borrow_mut();
+            if left.is_some() {
+                data.left = left;
+            }
+            if right.is_some() {
+                data.right = right;
+            }
+            if data.left.is_some() && data.right.is_some() {
+                data.left.take().zip(data.right.take())
+            } else {
+                None
+            }
+        };
+        if let Some(values) = values {
+            if let Some(next) = self.take_next() {
+                next.set_value(values);
+            }
+        }
+    }
+
+    fn set_error(&self, error: Next::Error) {
+        if let Some(next) = self.take_next() {
+            self.stop_source.request_stop();
+            next.set_error(error);
+        }
+    }
+
+    fn set_cancelled(&self) {
+        if let Some(next) = self.take_next() {
+            self.stop_source.request_stop();
+            next.set_cancelled();
+        }
+    }
+}
+
+struct LeftReceiver<A, B, Next> {
+    state: Rc<SharedState<A, B, Next>>,
+}
+
+impl<A, B, Next> Receiver for LeftReceiver<A, B, Next>
+where
+    Next: Receiver<Input = (A, B)>,
+{
+    type Input = A;
+    type Error = Next::Error;
+
+    fn set_value(self, value: Self::Input) {
+        self.state.set_value(Some(value), None);
+    }
+
+    fn set_error(self, error: Self::Error) {
+        self.state.set_error(error);
+    }
+
+    fn set_cancelled(self) {
+        self.state.set_cancelled();
+    }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+
+    fn get_env(&self) -> Env {
+        self.state.env.clone()
+    }
+}
+
+struct RightReceiver<A, B, Next, E> {
+    state: Rc<SharedState<A, B, Next>>,
+    _phantom: PhantomData<E>,
+}
+
+impl<A, B, Next, E> Receiver for RightReceiver<A, B, Next, E>
+where
+    Next: Receiver<Input = (A, B)>,
+    E: Into<Next::Error>,
+{
+    type Input = B;
+    type Error = E;
+
+    fn set_value(self, value: Self::Input) {
+        self.state.set_value(None, Some(value));
+    }
+
+    fn set_error(self, error: Self::Error) {
+        self.state.set_error(error.into());
+    }
+
+    fn set_cancelled(self) {
+        self.state.set_cancelled();
+    }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+
+    fn get_env(&self) -> Env {
+        self.state.env.clone()
+    }
+}
@@ -1,48 +1,115 @@
-use crate::traits::{Receiver, Work};
-use std::collections::VecDeque;
-use std::sync::Arc;
+use crate::consumers::sync_wait::WaitResult;
+use crate::traits::{Channel, Receiver, Sender, Work};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
 use crate::priv_sync::{Condvar, Mutex};
+use alloc::boxed::Box;
 
-type QueueType = VecDeque<Box<dyn FnOnce() + Send>>;
+// Invoked with `true` to run the item, or `false` if the executor has been shut down.
+type Task = Box<dyn FnOnce(bool) + Send>;
+
+struct Data {
+    queue: VecDeque<Task>,
+    stopped: bool,
+    shutdown: bool,
+}
 
 struct Inner {
-    queue: Mutex<QueueType>,
+    data: Mutex<Data>,
     cond_var: Condvar,
 }
 
 impl Inner {
     pub fn new() -> Self {
         Self {
-            queue: Mutex::new(QueueType::with_capacity(256)),
+            data: Mutex::new(Data {
+                queue: VecDeque::new(),
+                stopped: false,
+                shutdown: false,
+            }),
             cond_var: Condvar::new(),
         }
     }
 
-    pub fn add<F: 'static + FnOnce() + Send>(&self, work: F) {
+    pub fn add<F: 'static + FnOnce(bool) + Send>(&self, work: F) {
         {
-            let mut queue = self.queue.lock();
-            queue.push_back(Box::new(work));
+            let mut data = self.data.lock();
+            if !data.shutdown {
+                data.queue.push_back(Box::new(work));
+                drop(data);
+                self.cond_var.notify_one();
+                return;
+            }
         }
-        self.cond_var.notify_one();
+        work(false);
     }
 
     pub fn run_one(&self) -> bool {
         let to_run = {
-            let guard = self.queue.lock();
-            let mut guard = self.cond_var.wait_while(guard, |x| x.is_empty());
-            guard.pop_front()
+            let guard = self.data.lock();
+            let mut guard = self
+                .cond_var
+                .wait_while(guard, |x| x.queue.is_empty() && !x.stopped);
+            guard.queue.pop_front()
         };
+        Self::run(to_run)
+    }
 
+    pub fn try_run_one(&self) -> bool {
+        let to_run = self.data.lock().queue.pop_front();
+        Self::run(to_run)
+    }
+
+    // Runs one item, waiting at most until `deadline` for it to be queued.
+    pub fn run_one_before(&self, deadline: Instant) -> bool {
+        let to_run = {
+            let mut guard = self.data.lock();
+            loop {
+                let now = Instant::now();
+                if !guard.queue.is_empty() || guard.stopped || now >= deadline {
+                    break guard.queue.pop_front();
+                }
+                guard = self.cond_var.wait_timeout(guard, deadline - now);
+            }
+        };
+        Self::run(to_run)
+    }
+
+    fn run(to_run: Option<Task>) -> bool {
         if let Some(to_run) = to_run {
-            to_run();
+            to_run(true);
             true
         } else {
             false
         }
     }
+
+    fn stop(&self) {
+        self.data.lock().stopped = true;
+        self.cond_var.notify_all();
+    }
+
+    fn shutdown(&self) {
+        let pending = {
+            let mut data = self.data.lock();
+            data.stopped = true;
+            data.shutdown = true;
+            core::mem::take(&mut data.queue)
+        };
+        self.cond_var.notify_all();
+        // Outside of the lock, cancelled receivers may try to schedule more work.
+        for task in pending {
+            task(false);
+        }
+    }
 }
 
+/// An executor whose queued work is run by explicitly calling methods on its [`Runner`]s.
+///
+/// Dropping the executor shuts it down, see [`shutdown()`](ManualExecutor::shutdown).
 pub struct ManualExecutor {
     inner: Arc<Inner>,
 }
@@ -71,6 +138,31 @@ impl ManualExecutor {
             inner: self.inner.clone(),
         }
     }
+
+    /// Stops the runners of this executor.
+    ///
+    /// Runners blocked in [`run_one()`](Runner::run_one) are woken up, and from now on the blocking
+    /// methods of [`Runner`] return once the queue is empty instead of waiting for more work. Work
+    /// is still accepted and can be run.
+    pub fn stop(&self) {
+        self.inner.stop();
+    }
+
+    /// Shuts the executor down.
+    ///
+    /// Stops the runners like [`stop()`](ManualExecutor::stop), and completes the receivers of all
+    /// queued senders with `set_cancelled`. Senders started after shutdown are cancelled right
+    /// away, and work passed to [`execute()`](crate::traits::Scheduler::execute) is cancelled with
+    /// [`Work::cancel()`] instead of being run.
+    pub fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+}
+
+impl Drop for ManualExecutor {
+    fn drop(&mut self) {
+        self.inner.shutdown();
+    }
 }
 
 #[derive(Clone)]
@@ -79,9 +171,157 @@ pub struct Runner {
 }
 
 impl Runner {
+    /// Runs the next queued item, blocking until one is available.
+    ///
+    /// Returns `false` without running anything if the executor has been stopped and the queue
+    /// is empty.
     pub fn run_one(&self) -> bool {
         self.inner.run_one()
     }
+
+    /// Runs items until the executor is stopped or shut down, and returns the number of items
+    /// run.
+    ///
+    /// Items queued before [`stop()`](ManualExecutor::stop) are run before returning.
+    pub fn run(&self) -> usize {
+        let mut count = 0;
+        while self.run_one() {
+            count += 1;
+        }
+        count
+    }
+
+    /// Runs the next queued item if there is one, returns `false` without blocking otherwise.
+    pub fn try_run_one(&self) -> bool {
+        self.inner.try_run_one()
+    }
+
+    /// Runs queued items until the queue is empty and returns the number of items run.
+    ///
+    /// Items queued by the items being run are also run.
+    pub fn run_until_idle(&self) -> usize {
+        let mut count = 0;
+        while self.try_run_one() {
+            count += 1;
+        }
+        count
+    }
+
+    /// Runs items for `duration`, waiting for more items when the queue is empty, and returns the
+    /// number of items run.
+    ///
+    /// An item that is running when `duration` has passed is completed before returning.
+    pub fn run_for(&self, duration: Duration) -> usize {
+        let deadline = Instant::now() + duration;
+        let mut count = 0;
+        while self.inner.run_one_before(deadline) {
+            count += 1;
+        }
+        count
+    }
+
+    /// Starts `sender` and runs items until it completes.
+    ///
+    /// Returns the result of `sender` together with the number of items run. The items must be
+    /// able to complete `sender` or drop its receiver, or this blocks forever.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use txrx::manual_executor::ManualExecutor;
+    /// use txrx::traits::Scheduler;
+    /// use txrx::SenderExt;
+    ///
+    /// let executor = ManualExecutor::new();
+    /// let sender = executor.scheduler().schedule().map(|_| 10);
+    /// let (result, count) = executor.runner().run_until(sender);
+    /// assert_eq!((result.unwrap(), count), (10, 1));
+    /// ```
+    pub fn run_until<S>(&self, sender: S) -> (WaitResult<S::Output, S::Error>, usize)
+    where
+        S: Sender,
+    {
+        let state = Arc::new(RunUntilState {
+            result: Mutex::new(None),
+            done: AtomicBool::new(false),
+            inner: self.inner.clone(),
+        });
+        sender.start(RunUntilReceiver {
+            state: Some(state.clone()),
+        });
+
+        let mut count = 0;
+        loop {
+            if state.done.load(Ordering::Acquire) {
+                let result = state.result.lock().take().unwrap();
+                return (result, count);
+            }
+            let to_run = {
+                let guard = self.inner.data.lock();
+                let mut guard = self.inner.cond_var.wait_while(guard, |x| {
+                    x.queue.is_empty() && !state.done.load(Ordering::Acquire)
+                });
+                guard.queue.pop_front()
+            };
+            if Inner::run(to_run) {
+                count += 1;
+            }
+        }
+    }
+}
+
+struct RunUntilState<T, E> {
+    result: Mutex<Option<WaitResult<T, E>>>,
+    done: AtomicBool,
+    inner: Arc<Inner>,
+}
+
+impl<T, E> RunUntilState<T, E> {
+    fn set_result(&self, result: WaitResult<T, E>) {
+        *self.result.lock() = Some(result);
+        // Set under the queue lock so a waiting runner can't miss the notification.
+        let _data = self.inner.data.lock();
+        self.done.store(true, Ordering::Release);
+        self.inner.cond_var.notify_all();
+    }
+}
+
+struct RunUntilReceiver<T, E> {
+    // Taken when the receiver is completed, reports abandonment if still set on drop.
+    state: Option<Arc<RunUntilState<T, E>>>,
+}
+
+impl<T, E> RunUntilReceiver<T, E> {
+    fn set_result(mut self, result: WaitResult<T, E>) {
+        if let Some(state) = self.state.take() {
+            state.set_result(result);
+        }
+    }
+}
+
+impl<T, E> Drop for RunUntilReceiver<T, E> {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            state.set_result(WaitResult::Abandoned);
+        }
+    }
+}
+
+impl<T, E> Receiver for RunUntilReceiver<T, E> {
+    type Input = T;
+    type Error = E;
+
+    fn set_value(self, value: Self::Input) {
+        self.set_result(WaitResult::Value(value));
+    }
+
+    fn set_error(self, error: Self::Error) {
+        self.set_result(WaitResult::Error(error));
+    }
+
+    fn set_cancelled(self) {
+        self.set_result(WaitResult::Cancelled);
+    }
 }
 
 pub struct ScheduledSender {
@@ -90,21 +330,26 @@ pub struct ScheduledSender {
 
 impl crate::traits::Sender for ScheduledSender {
     type Output = ();
+    type Error = crate::Error;
     type Scheduler = Scheduler;
 
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
-        self.inner.add(move || {
-            receiver.set_value(());
+        self.inner.add(move |run| {
+            if !run || receiver.get_stop_token().stop_requested() {
+                receiver.set_cancelled();
+            } else {
+                receiver.set_value(());
+            }
         });
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        Self::Scheduler {
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(Self::Scheduler {
             inner: self.inner.clone(),
-        }
+        })
     }
 }
 
@@ -126,8 +371,87 @@ impl crate::traits::Scheduler for Scheduler {
     where
         W: 'static + Send + Work,
     {
-        self.inner.add(move || {
-            work.execute();
+        self.inner.add(move |run| {
+            if run {
+                work.execute();
+            } else {
+                work.cancel();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ManualExecutor;
+    use crate::traits::Scheduler;
+    use crate::SenderExt;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn non_blocking_runs() {
+        let executor = ManualExecutor::new();
+        let runner = executor.runner();
+        assert!(!runner.try_run_one());
+        assert_eq!(runner.run_until_idle(), 0);
+
+        let mut scheduler = executor.scheduler();
+        let nested = scheduler.clone();
+        scheduler.execute(move || {
+            let mut nested = nested;
+            nested.execute(|| {});
         });
+        assert_eq!(runner.run_until_idle(), 2);
+
+        let start = Instant::now();
+        assert_eq!(runner.run_for(Duration::from_millis(10)), 0);
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+
+    #[test]
+    fn run_until() {
+        let executor = ManualExecutor::new();
+        let runner = executor.runner();
+        let mut scheduler = executor.scheduler();
+        let sender = scheduler
+            .schedule()
+            .and_then(move |_| scheduler.schedule().map(|_| "done"));
+        let (result, count) = runner.run_until(sender);
+        assert_eq!((result.unwrap(), count), ("done", 2));
+
+        // Completes from another thread while the runner is waiting.
+        let (sender, trigger) = crate::test::ManualSender::new();
+        let thread = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            trigger.trigger();
+        });
+        assert!(runner.run_until(sender).0.is_value());
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn stop_wakes_runner() {
+        let executor = ManualExecutor::new();
+        let runner = executor.runner();
+        let thread = std::thread::spawn(move || runner.run());
+        std::thread::sleep(Duration::from_millis(10));
+        executor.scheduler().execute(|| {});
+        executor.stop();
+        assert_eq!(thread.join().unwrap(), 1);
+        assert!(!executor.runner().run_one());
+    }
+
+    #[test]
+    fn shutdown_cancels_pending() {
+        let executor = ManualExecutor::new();
+        let mut scheduler = executor.scheduler();
+        let pending = scheduler.schedule().map(|_| 1);
+        let thread = std::thread::spawn(move || pending.sync_wait());
+        std::thread::sleep(Duration::from_millis(10));
+        drop(executor);
+        assert!(thread.join().unwrap().is_cancelled());
+
+        // Work started after shutdown is rejected.
+        assert!(scheduler.schedule().sync_wait().is_cancelled());
     }
 }
@@ -1,75 +1,79 @@
-use std::hint::unreachable_unchecked;
+#[cfg(feature = "std")]
+pub use blocking::Condvar;
+#[cfg(feature = "std")]
+pub use std_mutex::{Mutex, MutexGuard};
 
-pub type MutexGuard<'a, T> = std::sync::MutexGuard<'a, T>;
+#[cfg(not(feature = "std"))]
+pub use spin::{Mutex, MutexGuard};
 
-pub struct Mutex<T> {
-    inner: std::sync::Mutex<T>,
-}
+#[cfg(not(feature = "std"))]
+mod spin;
 
-impl<T> Mutex<T> {
-    pub fn new(value: T) -> Self {
-        Self {
-            inner: std::sync::Mutex::new(value),
-        }
-    }
+#[cfg(feature = "std")]
+mod std_mutex {
+    pub type MutexGuard<'a, T> = std::sync::MutexGuard<'a, T>;
 
-    pub fn lock(&self) -> MutexGuard<T> {
-        self.inner.lock().unwrap()
+    pub struct Mutex<T> {
+        inner: std::sync::Mutex<T>,
     }
-}
 
-pub struct Condvar {
-    inner: std::sync::Condvar,
-}
+    impl<T> Mutex<T> {
+        pub const fn new(value: T) -> Self {
+            Self {
+                inner: std::sync::Mutex::new(value),
+            }
+        }
 
-impl Condvar {
-    pub fn new() -> Self {
-        Self {
-            inner: std::sync::Condvar::new(),
+        pub fn lock(&self) -> MutexGuard<'_, T> {
+            self.inner.lock().unwrap()
         }
     }
+}
 
-    pub fn wait_while<'a, T, F>(&self, lock: MutexGuard<'a, T>, predicate: F) -> MutexGuard<'a, T>
-    where
-        F: FnMut(&mut T) -> bool,
-    {
-        self.inner.wait_while(lock, predicate).unwrap()
-    }
+#[cfg(feature = "std")]
+mod blocking {
+    use super::MutexGuard;
 
-    pub fn notify_one(&self) {
-        self.inner.notify_one();
+    pub struct Condvar {
+        inner: std::sync::Condvar,
     }
-}
 
-pub struct AsyncValue<T> {
-    value: Mutex<Option<T>>,
-    cv: Condvar,
-}
+    impl Condvar {
+        pub fn new() -> Self {
+            Self {
+                inner: std::sync::Condvar::new(),
+            }
+        }
 
-impl<T> AsyncValue<T> {
-    pub fn new() -> Self {
-        Self {
-            value: Mutex::new(None),
-            cv: Condvar::new(),
+        pub fn wait_while<'a, T, F>(
+            &self,
+            lock: MutexGuard<'a, T>,
+            predicate: F,
+        ) -> MutexGuard<'a, T>
+        where
+            F: FnMut(&mut T) -> bool,
+        {
+            self.inner.wait_while(lock, predicate).unwrap()
         }
-    }
 
-    pub fn take(&self) -> T {
-        let lock = self.value.lock();
-        let mut lock = self.cv.wait_while(lock, |x| x.is_none());
+        pub fn wait<'a, T>(&self, lock: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+            self.inner.wait(lock).unwrap()
+        }
 
-        if let Some(x) = lock.take() {
-            x
-        } else {
-            unsafe { unreachable_unchecked() }
+        pub fn wait_timeout<'a, T>(
+            &self,
+            lock: MutexGuard<'a, T>,
+            timeout: std::time::Duration,
+        ) -> MutexGuard<'a, T> {
+            self.inner.wait_timeout(lock, timeout).unwrap().0
         }
-    }
 
-    pub fn set(&self, value: T) {
-        {
-            let mut lock = self.value.lock();
-            *lock = Some(value);
+        pub fn notify_one(&self) {
+            self.inner.notify_one();
+        }
+
+        pub fn notify_all(&self) {
+            self.inner.notify_all();
         }
-        self.cv.notify_one();
     }
 }
@@ -0,0 +1,125 @@
+//! A scheduler that runs work on the thread that drives it.
+//!
+//! [`sync_wait()`](crate::sync_wait) drives a [`RunLoop`] while it waits and makes its
+//! [`RunLoopScheduler`] available through the [environment](crate::env) of its receiver. Work
+//! scheduled on it runs on the waiting thread, so a pipeline can transfer back to the thread that
+//! waits for it instead of deadlocking.
+//!
+//! ## Examples
+//!
+//! ```
+//! use txrx::factories::read_env;
+//! use txrx::run_loop::RunLoopScheduler;
+//! use txrx::traits::Scheduler;
+//! use txrx::SenderExt;
+//!
+//! let thread = std::thread::spawn(|| {
+//!     read_env::<RunLoopScheduler>()
+//!         .and_then(|scheduler: Option<RunLoopScheduler>| scheduler.unwrap().schedule())
+//!         .map(|_| std::thread::current().id())
+//!         .sync_wait()
+//!         .unwrap()
+//! });
+//! let waiting_thread = thread.thread().id();
+//! assert_eq!(thread.join().unwrap(), waiting_thread);
+//! ```
+use crate::manual_executor::{self, ManualExecutor, ScheduledSender};
+use crate::traits::{Scheduler, Work};
+use std::time::Duration;
+
+/// A queue of work that is run by calling [`run()`](RunLoop::run) until
+/// [`finish()`](RunLoop::finish) is called.
+///
+/// Dropping the run loop cancels work that is still queued, see
+/// [`ManualExecutor::shutdown()`].
+pub struct RunLoop {
+    executor: ManualExecutor,
+}
+
+impl Default for RunLoop {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunLoop {
+    pub fn new() -> Self {
+        Self {
+            executor: ManualExecutor::new(),
+        }
+    }
+
+    pub fn scheduler(&self) -> RunLoopScheduler {
+        RunLoopScheduler {
+            inner: self.executor.scheduler(),
+        }
+    }
+
+    /// Runs queued work on the current thread until [`finish()`](RunLoop::finish) has been called
+    /// and the queue is empty, and returns the number of items run.
+    pub fn run(&self) -> usize {
+        self.executor.runner().run()
+    }
+
+    /// Same as [`run()`](RunLoop::run), but returns after at most `duration`.
+    pub fn run_for(&self, duration: Duration) -> usize {
+        self.executor.runner().run_for(duration)
+    }
+
+    /// Makes [`run()`](RunLoop::run) return once the queue is empty. Can be called from any thread.
+    pub fn finish(&self) {
+        self.executor.stop();
+    }
+
+    /// Finishes the run loop and cancels all queued work and work that is scheduled later, the
+    /// same as dropping it.
+    pub fn shutdown(&self) {
+        self.executor.shutdown();
+    }
+}
+
+/// Scheduler for a [`RunLoop`].
+#[derive(Clone)]
+pub struct RunLoopScheduler {
+    inner: manual_executor::Scheduler,
+}
+
+impl Scheduler for RunLoopScheduler {
+    type Sender = ScheduledSender;
+
+    fn schedule(&mut self) -> Self::Sender {
+        self.inner.schedule()
+    }
+
+    fn execute<W>(&mut self, work: W)
+    where
+        W: 'static + Send + Work,
+    {
+        self.inner.execute(work);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RunLoop;
+    use crate::traits::Scheduler;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn run_until_finished() {
+        let run_loop = Arc::new(RunLoop::new());
+        let mut scheduler = run_loop.scheduler();
+        scheduler.execute(|| {});
+        let thread = std::thread::spawn({
+            let run_loop = run_loop.clone();
+            move || {
+                std::thread::sleep(Duration::from_millis(10));
+                scheduler.execute(|| {});
+                run_loop.finish();
+            }
+        });
+        assert_eq!(run_loop.run(), 2);
+        thread.join().unwrap();
+    }
+}
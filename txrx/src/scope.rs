@@ -0,0 +1,387 @@
+//! Senders that may borrow from the stack frame that creates them.
+//!
+//! Senders normally have to be `'static`, since nothing stops them from running after the caller
+//! returns. Within [`scope()`] work is created from closures that only have to outlive the scope,
+//! in the same way as with [`std::thread::scope()`]. The senders themselves are `'static` and can
+//! be combined and waited on like any other sender.
+//!
+//! When the scope ends, work that hasn't started running yet is dropped and its receiver is
+//! cancelled once the scheduler gets to it. The scope blocks until all work that is already running
+//! has finished, so borrowed data is never accessed after the scope returns.
+//!
+//! ## Examples
+//!
+//! ```
+//! use txrx::manual_executor::ManualExecutor;
+//! use txrx::SenderExt;
+//!
+//! let executor = ManualExecutor::new();
+//! let runner = executor.runner();
+//! std::thread::spawn(move || while runner.run_one() {});
+//!
+//! let data = vec![1, 2, 3, 4];
+//! let sum = std::sync::atomic::AtomicI32::new(0);
+//! txrx::scope(|s| {
+//!     let work = s.bulk(executor.scheduler(), data.len(), |i| {
+//!         sum.fetch_add(data[i], std::sync::atomic::Ordering::Relaxed);
+//!     });
+//!     let total = s.execute_on(executor.scheduler(), || data.iter().sum::<i32>());
+//!     let (_, total) = work.when_both(total).sync_wait().unwrap();
+//!     assert_eq!(total, 10);
+//! });
+//! assert_eq!(sum.into_inner(), 10);
+//! ```
+use crate::priv_sync::{Condvar, Mutex};
+use crate::traits::{Channel, Receiver, Scheduler, Sender};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+type ScopedFn<T> = Box<dyn FnOnce() -> T + Send>;
+type ScopedBulkFn = Arc<dyn Fn(usize) + Send + Sync>;
+
+trait Slot: Send + Sync {
+    fn clear(&self);
+}
+
+impl<T: Send> Slot for Mutex<Option<T>> {
+    fn clear(&self) {
+        let value = self.lock().take();
+        drop(value);
+    }
+}
+
+struct ScopeData {
+    closed: bool,
+    running: usize,
+    slots: Vec<Arc<dyn Slot>>,
+}
+
+struct ScopeState {
+    data: Mutex<ScopeData>,
+    cond_var: Condvar,
+}
+
+impl ScopeState {
+    fn add_slot<T: 'static + Send>(&self, value: T) -> Arc<Mutex<Option<T>>> {
+        let slot = Arc::new(Mutex::new(Some(value)));
+        let mut data = self.data.lock();
+        if data.closed {
+            // Only reachable from work that is still running inside a closing scope.
+            slot.clear();
+        } else {
+            data.slots.push(slot.clone());
+        }
+        slot
+    }
+
+    // Returns the value of `slot` if the scope is still open, marking it as running until the
+    // guard is dropped.
+    fn enter<T>(
+        self: &Arc<Self>,
+        slot: &Mutex<Option<T>>,
+        take: impl FnOnce(&mut Option<T>) -> Option<T>,
+    ) -> Option<(T, RunningGuard)> {
+        let mut data = self.data.lock();
+        if data.closed {
+            return None;
+        }
+        let value = take(&mut slot.lock())?;
+        data.running += 1;
+        Some((
+            value,
+            RunningGuard {
+                state: self.clone(),
+            },
+        ))
+    }
+
+    fn close(&self) {
+        let slots = {
+            let mut data = self.data.lock();
+            data.closed = true;
+            core::mem::take(&mut data.slots)
+        };
+        // Drop all work that never started while the borrowed data is still alive.
+        for slot in slots {
+            slot.clear();
+        }
+        let data = self.data.lock();
+        let _data = self.cond_var.wait_while(data, |x| x.running > 0);
+    }
+}
+
+struct RunningGuard {
+    state: Arc<ScopeState>,
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        let mut data = self.state.data.lock();
+        data.running -= 1;
+        if data.running == 0 {
+            self.state.cond_var.notify_all();
+        }
+    }
+}
+
+// Closes the scope when it goes out of scope, also when unwinding.
+struct CloseGuard<'a> {
+    state: &'a ScopeState,
+}
+
+impl Drop for CloseGuard<'_> {
+    fn drop(&mut self) {
+        self.state.close();
+    }
+}
+
+/// A scope for creating senders that borrow non-`'static` data. See [`scope()`].
+pub struct Scope<'scope, 'env: 'scope> {
+    state: Arc<ScopeState>,
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+/// Creates a scope in which senders may borrow data that outlives the scope.
+///
+/// All work created through the [`Scope`] has either completed or been dropped when this function
+/// returns. See the [module documentation](crate::scope) for details.
+pub fn scope<'env, F, T>(f: F) -> T
+where
+    F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T,
+{
+    let scope = Scope {
+        state: Arc::new(ScopeState {
+            data: Mutex::new(ScopeData {
+                closed: false,
+                running: 0,
+                slots: Vec::new(),
+            }),
+            cond_var: Condvar::new(),
+        }),
+        scope: PhantomData,
+        env: PhantomData,
+    };
+    let _guard = CloseGuard {
+        state: &scope.state,
+    };
+    f(&scope)
+}
+
+impl<'scope> Scope<'scope, '_> {
+    /// Returns a sender that invokes `func` on `scheduler` and sends the returned value.
+    ///
+    /// The sender is cancelled if it hasn't started running when the scope ends.
+    pub fn execute_on<Sched, F, T>(
+        &'scope self,
+        scheduler: Sched,
+        func: F,
+    ) -> ScopedSender<Sched, T>
+    where
+        Sched: 'static + Clone + Send + Scheduler,
+        F: 'scope + Send + FnOnce() -> T,
+        T: 'static + Send,
+    {
+        let func: Box<dyn FnOnce() -> T + Send + 'scope> = Box::new(func);
+        // Safety: the function is only invoked while the scope is open, and dropped at the latest
+        // when the scope is closed. The scope can't be closed before the end of 'scope.
+        let func: ScopedFn<T> = unsafe { core::mem::transmute(func) };
+        ScopedSender {
+            scheduler,
+            state: self.state.clone(),
+            slot: self.state.add_slot(func),
+        }
+    }
+
+    /// Returns a sender that invokes `func` with every index in `0..size` on `scheduler`.
+    ///
+    /// Indices that haven't started running when the scope ends are skipped, and the sender is
+    /// cancelled.
+    pub fn bulk<Sched, F>(&'scope self, scheduler: Sched, size: usize, func: F) -> ScopedBulk<Sched>
+    where
+        Sched: 'static + Clone + Send + Scheduler,
+        F: 'scope + Send + Sync + Fn(usize),
+    {
+        let func: Arc<dyn Fn(usize) + Send + Sync + 'scope> = Arc::new(func);
+        // Safety: see `execute_on()`. Clones of the function are only handed out while the scope
+        // is open, and are dropped before the running count is released.
+        let func: ScopedBulkFn = unsafe { core::mem::transmute(func) };
+        ScopedBulk {
+            scheduler,
+            size,
+            state: self.state.clone(),
+            slot: self.state.add_slot(func),
+        }
+    }
+}
+
+/// Sender returned by [`Scope::execute_on()`].
+pub struct ScopedSender<Sched, T> {
+    scheduler: Sched,
+    state: Arc<ScopeState>,
+    slot: Arc<Mutex<Option<ScopedFn<T>>>>,
+}
+
+impl<Sched, T> Sender for ScopedSender<Sched, T>
+where
+    Sched: 'static + Clone + Send + Scheduler,
+    T: 'static + Send,
+{
+    type Output = T;
+    type Error = crate::Error;
+    type Scheduler = Sched;
+
+    fn start<R>(mut self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        let state = self.state;
+        let slot = self.slot;
+        self.scheduler.execute(move || {
+            if receiver.get_stop_token().stop_requested() {
+                receiver.set_cancelled();
+                return;
+            }
+            match state.enter(&slot, Option::take) {
+                Some((func, running)) => {
+                    let value = func();
+                    drop(running);
+                    receiver.set_value(value);
+                }
+                None => receiver.set_cancelled(),
+            }
+        });
+    }
+
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(self.scheduler.clone())
+    }
+}
+
+/// Sender returned by [`Scope::bulk()`].
+pub struct ScopedBulk<Sched> {
+    scheduler: Sched,
+    size: usize,
+    state: Arc<ScopeState>,
+    slot: Arc<Mutex<Option<ScopedBulkFn>>>,
+}
+
+struct BulkState<R> {
+    receiver: Mutex<Option<R>>,
+    remaining: AtomicUsize,
+    skipped: AtomicBool,
+}
+
+impl<R: Receiver<Input = ()>> BulkState<R> {
+    fn finish_one(&self) {
+        if self.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+            if let Some(receiver) = self.receiver.lock().take() {
+                if self.skipped.load(Ordering::Acquire) {
+                    receiver.set_cancelled();
+                } else {
+                    receiver.set_value(());
+                }
+            }
+        }
+    }
+}
+
+impl<Sched> Sender for ScopedBulk<Sched>
+where
+    Sched: 'static + Clone + Send + Scheduler,
+{
+    type Output = ();
+    type Error = crate::Error;
+    type Scheduler = Sched;
+
+    fn start<R>(mut self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        if self.size == 0 {
+            self.slot.clear();
+            receiver.set_value(());
+            return;
+        }
+
+        let bulk_state = Arc::new(BulkState {
+            receiver: Mutex::new(Some(receiver)),
+            remaining: AtomicUsize::new(self.size),
+            skipped: AtomicBool::new(false),
+        });
+        for i in 0..self.size {
+            let state = self.state.clone();
+            let slot = self.slot.clone();
+            let bulk_state = bulk_state.clone();
+            self.scheduler.execute(move || {
+                let stop_requested = bulk_state
+                    .receiver
+                    .lock()
+                    .as_ref()
+                    .is_some_and(|x| x.get_stop_token().stop_requested());
+                match state.enter(&slot, |x| x.clone()) {
+                    Some((func, running)) if !stop_requested => {
+                        func(i);
+                        drop(func);
+                        drop(running);
+                    }
+                    _ => bulk_state.skipped.store(true, Ordering::Release),
+                }
+                bulk_state.finish_one();
+            });
+        }
+    }
+
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(self.scheduler.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manual_executor::ManualExecutor;
+    use crate::SenderExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    #[test]
+    fn borrows() {
+        let executor = ManualExecutor::new();
+        let runner = executor.runner();
+        std::thread::spawn(move || while runner.run_one() {});
+
+        let mut data = vec![1, 2, 3];
+        let count = AtomicUsize::new(0);
+        let len = super::scope(|s| {
+            let bulk = s.bulk(executor.scheduler(), data.len(), |i| {
+                count.fetch_add(data[i], Ordering::Relaxed);
+            });
+            bulk.when_both(s.execute_on(executor.scheduler(), || data.len()))
+                .map(|(_, len)| len)
+                .sync_wait()
+                .unwrap()
+        });
+        assert_eq!(len, 3);
+        assert_eq!(count.load(Ordering::Relaxed), 6);
+        data.push(4);
+    }
+
+    #[test]
+    fn not_started_is_cancelled() {
+        let executor = ManualExecutor::new();
+        let runner = executor.runner();
+        let called = Mutex::new(false);
+        let sender = super::scope(|s| {
+            s.execute_on(executor.scheduler(), || {
+                *called.lock().unwrap() = true;
+            })
+        });
+        let awaitable = std::thread::spawn(move || sender.sync_wait());
+        runner.run_one();
+        assert!(awaitable.join().unwrap().is_cancelled());
+        assert!(!*called.lock().unwrap());
+    }
+}
@@ -0,0 +1,234 @@
+//! Cooperative cancellation of in-flight work.
+//!
+//! A [`StopSource`] owns the stop state, and hands out [`StopToken`]s that can be queried by the
+//! work that should be stopped. Receivers expose a token through
+//! [`Receiver::get_stop_token()`](crate::traits::Receiver::get_stop_token), which lets a sender
+//! find out if the consumer is still interested in its result.
+//!
+//! ## Examples
+//!
+//! ```
+//! use txrx::stop_token::StopSource;
+//!
+//! let source = StopSource::new();
+//! let token = source.token();
+//! assert!(!token.stop_requested());
+//! assert!(source.request_stop());
+//! assert!(token.stop_requested());
+//! ```
+use crate::priv_sync::Mutex;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+type Callback = Box<dyn FnOnce() + Send>;
+
+struct Callbacks {
+    next_id: usize,
+    list: Vec<(usize, Callback)>,
+}
+
+struct StopState {
+    stopped: AtomicBool,
+    callbacks: Mutex<Callbacks>,
+}
+
+impl StopState {
+    fn new() -> Self {
+        Self {
+            stopped: AtomicBool::new(false),
+            callbacks: Mutex::new(Callbacks {
+                next_id: 0,
+                list: Vec::new(),
+            }),
+        }
+    }
+
+    fn stop_requested(&self) -> bool {
+        self.stopped.load(Ordering::Acquire)
+    }
+
+    fn request_stop(&self) -> bool {
+        let callbacks = {
+            let mut lock = self.callbacks.lock();
+            if self.stopped.swap(true, Ordering::AcqRel) {
+                return false;
+            }
+            core::mem::take(&mut lock.list)
+        };
+
+        // Run callbacks outside of the lock so they are free to register or drop other callbacks.
+        for (_, callback) in callbacks {
+            callback();
+        }
+        true
+    }
+
+    fn register(&self, callback: Callback) -> Option<usize> {
+        let mut lock = self.callbacks.lock();
+        if self.stop_requested() {
+            drop(lock);
+            callback();
+            None
+        } else {
+            let id = lock.next_id;
+            lock.next_id += 1;
+            lock.list.push((id, callback));
+            Some(id)
+        }
+    }
+
+    fn deregister(&self, id: usize) {
+        let callback = {
+            let mut lock = self.callbacks.lock();
+            lock.list
+                .iter()
+                .position(|(x, _)| *x == id)
+                .map(|pos| lock.list.swap_remove(pos))
+        };
+        // Drop the callback outside of the lock.
+        drop(callback);
+    }
+}
+
+/// The owner of a stop state, used to request that work stops.
+#[derive(Clone)]
+pub struct StopSource {
+    state: Arc<StopState>,
+}
+
+impl Default for StopSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StopSource {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(StopState::new()),
+        }
+    }
+
+    /// Returns a token associated with this source.
+    pub fn token(&self) -> StopToken {
+        StopToken {
+            state: Some(self.state.clone()),
+        }
+    }
+
+    /// Requests stop and invokes all registered callbacks.
+    ///
+    /// Returns `true` if this call made the stop request, `false` if stop was already requested.
+    pub fn request_stop(&self) -> bool {
+        self.state.request_stop()
+    }
+
+    pub fn stop_requested(&self) -> bool {
+        self.state.stop_requested()
+    }
+}
+
+/// A token that can be used to query if stop has been requested.
+///
+/// The default token is never stopped.
+#[derive(Clone, Default)]
+pub struct StopToken {
+    state: Option<Arc<StopState>>,
+}
+
+impl StopToken {
+    /// Returns a token that can never be stopped.
+    pub fn never() -> Self {
+        Self { state: None }
+    }
+
+    pub fn stop_requested(&self) -> bool {
+        self.state.as_ref().is_some_and(|x| x.stop_requested())
+    }
+
+    /// Returns `false` if stop can never be requested through this token.
+    pub fn stop_possible(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// Registers `callback` to be invoked when stop is requested.
+    ///
+    /// If stop has already been requested `callback` is invoked immediately. The callback is
+    /// deregistered when the returned [`StopCallback`] is dropped.
+    pub fn on_stop<F>(&self, callback: F) -> StopCallback
+    where
+        F: 'static + Send + FnOnce(),
+    {
+        let registration = self.state.as_ref().and_then(|state| {
+            state
+                .register(Box::new(callback))
+                .map(|id| (state.clone(), id))
+        });
+        StopCallback { registration }
+    }
+}
+
+/// Guard for a callback registered with [`StopToken::on_stop()`].
+///
+/// Dropping the guard deregisters the callback.
+pub struct StopCallback {
+    registration: Option<(Arc<StopState>, usize)>,
+}
+
+impl Drop for StopCallback {
+    fn drop(&mut self) {
+        if let Some((state, id)) = self.registration.take() {
+            state.deregister(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn never_stops() {
+        let token = StopToken::never();
+        assert!(!token.stop_possible());
+        assert!(!token.stop_requested());
+        let _cb = token.on_stop(|| panic!("Never called"));
+    }
+
+    #[test]
+    fn callbacks() {
+        let called = Arc::new(AtomicUsize::new(0));
+        let source = StopSource::new();
+        let token = source.token();
+
+        let cb = {
+            let called = called.clone();
+            token.on_stop(move || {
+                called.fetch_add(1, Ordering::Relaxed);
+            })
+        };
+        let dropped = {
+            let called = called.clone();
+            token.on_stop(move || {
+                called.fetch_add(10, Ordering::Relaxed);
+            })
+        };
+        drop(dropped);
+
+        assert!(source.request_stop());
+        assert!(!source.request_stop());
+        assert_eq!(called.load(Ordering::Relaxed), 1);
+        drop(cb);
+
+        let _late = {
+            let called = called.clone();
+            token.on_stop(move || {
+                called.fetch_add(1, Ordering::Relaxed);
+            })
+        };
+        assert_eq!(called.load(Ordering::Relaxed), 2);
+    }
+}
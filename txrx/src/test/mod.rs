@@ -1,7 +1,24 @@
 use crate::priv_sync::Mutex;
 use crate::traits::{Receiver, Sender};
 use crate::ImmediateScheduler;
-use std::sync::Arc;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+#[cfg(feature = "std")]
+pub use virtual_time::{VirtualTimeScheduler, VirtualTimeSender};
+
+#[cfg(feature = "std")]
+mod virtual_time;
+
+#[cfg(all(test, feature = "std"))]
+pub(crate) use counting_alloc::allocations;
+#[cfg(test)]
+pub(crate) use stoppable::Stoppable;
+
+#[cfg(all(test, feature = "std"))]
+mod counting_alloc;
+#[cfg(test)]
+mod stoppable;
 
 pub struct ManualTrigger {
     trigger_function: Mutex<Box<dyn FnMut() + Send>>,
@@ -42,11 +59,12 @@ impl ManualSender {
 
 impl Sender for ManualSender {
     type Output = ();
+    type Error = crate::Error;
     type Scheduler = ImmediateScheduler;
 
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>,
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         let mut receiver = Some(receiver);
         self.trigger.set_trigger(move || {
@@ -55,10 +73,6 @@ impl Sender for ManualSender {
             }
         });
     }
-
-    fn get_scheduler(&self) -> Self::Scheduler {
-        ImmediateScheduler
-    }
 }
 
 #[cfg(test)]
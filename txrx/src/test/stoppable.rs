@@ -0,0 +1,80 @@
+use crate::consumers::sync_wait::WaitResult;
+use crate::priv_sync::Mutex;
+use crate::stop_token::{StopSource, StopToken};
+use crate::traits::{Receiver, Sender};
+use alloc::sync::Arc;
+
+type ResultSlot<T, E> = Arc<Mutex<Option<WaitResult<T, E>>>>;
+
+/// A started sender whose receiver gets its stop token from a stop source owned by the test.
+pub(crate) struct Stoppable<T, E> {
+    stop_source: StopSource,
+    result: ResultSlot<T, E>,
+}
+
+impl<T: 'static + Send, E: 'static + Send> Stoppable<T, E> {
+    pub(crate) fn start<S: Sender<Output = T, Error = E>>(sender: S) -> Self {
+        let stop_source = StopSource::new();
+        let result = Arc::new(Mutex::new(None));
+        sender.start(StoppableReceiver {
+            stop_token: stop_source.token(),
+            result: Some(result.clone()),
+        });
+        Self {
+            stop_source,
+            result,
+        }
+    }
+
+    pub(crate) fn request_stop(&self) {
+        self.stop_source.request_stop();
+    }
+
+    /// Takes the result, if the receiver has been completed or dropped.
+    pub(crate) fn take(&self) -> Option<WaitResult<T, E>> {
+        self.result.lock().take()
+    }
+}
+
+struct StoppableReceiver<T, E> {
+    stop_token: StopToken,
+    // Taken when the receiver is completed, reports abandonment if still set on drop.
+    result: Option<ResultSlot<T, E>>,
+}
+
+impl<T, E> StoppableReceiver<T, E> {
+    fn set_result(mut self, result: WaitResult<T, E>) {
+        if let Some(slot) = self.result.take() {
+            *slot.lock() = Some(result);
+        }
+    }
+}
+
+impl<T, E> Drop for StoppableReceiver<T, E> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.result.take() {
+            *slot.lock() = Some(WaitResult::Abandoned);
+        }
+    }
+}
+
+impl<T, E> Receiver for StoppableReceiver<T, E> {
+    type Input = T;
+    type Error = E;
+
+    fn set_value(self, value: Self::Input) {
+        self.set_result(WaitResult::Value(value));
+    }
+
+    fn set_error(self, error: Self::Error) {
+        self.set_result(WaitResult::Error(error));
+    }
+
+    fn set_cancelled(self) {
+        self.set_result(WaitResult::Cancelled);
+    }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.stop_token.clone()
+    }
+}
@@ -0,0 +1,216 @@
+use crate::priv_sync::Mutex;
+use crate::timer::{start_timer, TimerHost, TimerQueue, TimerWork};
+use crate::traits::{Channel, Receiver, Sender, TimeScheduler, Work};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+struct Data {
+    now: Instant,
+    queue: TimerQueue,
+}
+
+struct Inner {
+    data: Mutex<Data>,
+}
+
+impl TimerHost for Inner {
+    fn add(&self, deadline: Instant, work: TimerWork) -> u64 {
+        self.data.lock().queue.push(deadline, work)
+    }
+
+    fn cancel(&self, id: u64) {
+        let work = self.data.lock().queue.remove(id);
+        if let Some(work) = work {
+            work(false);
+        }
+    }
+}
+
+/// A [`TimeScheduler`] with a virtual clock, for deterministic tests of time-dependent code.
+///
+/// Nothing runs until the test calls [`advance()`](VirtualTimeScheduler::advance) or
+/// [`run_until_idle()`](VirtualTimeScheduler::run_until_idle), and all work runs on the calling
+/// thread. Work scheduled without a deadline runs at the current virtual time.
+///
+/// ## Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use txrx::test::{ManualSender, VirtualTimeScheduler};
+/// use txrx::SenderExt;
+///
+/// let scheduler = VirtualTimeScheduler::new();
+/// let (sender, _trigger) = ManualSender::new();
+/// let result = sender
+///     .timeout_on(scheduler.clone(), Duration::from_secs(10))
+///     .ensure_started();
+///
+/// assert_eq!(scheduler.advance(Duration::from_secs(9)), 0);
+/// assert_eq!(scheduler.advance(Duration::from_secs(1)), 1);
+/// assert!(result.sync_wait().unwrap_error().to_string().contains("timed out"));
+/// ```
+#[derive(Clone)]
+pub struct VirtualTimeScheduler {
+    inner: Arc<Inner>,
+}
+
+impl Default for VirtualTimeScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VirtualTimeScheduler {
+    /// Creates a scheduler whose virtual clock starts at the current time.
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                data: Mutex::new(Data {
+                    now: Instant::now(),
+                    queue: TimerQueue::new(),
+                }),
+            }),
+        }
+    }
+
+    /// Moves the virtual clock forward by `duration`, running all work that becomes due.
+    ///
+    /// Work runs in deadline order, and the clock is set to the deadline of each item while it
+    /// runs. Returns the number of items run.
+    pub fn advance(&self, duration: Duration) -> usize {
+        let target = self.inner.data.lock().now + duration;
+        let count = self.run_while(|_| Some(target));
+        self.inner.data.lock().now = target;
+        count
+    }
+
+    /// Runs all work, moving the virtual clock forward to each deadline, until no work is left.
+    ///
+    /// Returns the number of items run.
+    pub fn run_until_idle(&self) -> usize {
+        self.run_while(|queue| queue.next_deadline())
+    }
+
+    fn run_while(&self, until: impl Fn(&TimerQueue) -> Option<Instant>) -> usize {
+        let mut count = 0;
+        loop {
+            let work = {
+                let mut data = self.inner.data.lock();
+                let due = until(&data.queue).and_then(|until| data.queue.pop_due(until));
+                match due {
+                    Some((deadline, work)) => {
+                        data.now = data.now.max(deadline);
+                        work
+                    }
+                    None => return count,
+                }
+            };
+            // Run outside of the lock, the work is free to schedule more work.
+            work(true);
+            count += 1;
+        }
+    }
+}
+
+impl crate::traits::Scheduler for VirtualTimeScheduler {
+    type Sender = VirtualTimeSender;
+
+    fn schedule(&mut self) -> Self::Sender {
+        let now = self.now();
+        self.schedule_at(now)
+    }
+
+    fn execute<W>(&mut self, work: W)
+    where
+        W: 'static + Send + Work,
+    {
+        let now = self.now();
+        self.inner.add(
+            now,
+            Box::new(move |_| {
+                work.execute();
+            }),
+        );
+    }
+}
+
+impl TimeScheduler for VirtualTimeScheduler {
+    type TimedSender = VirtualTimeSender;
+
+    fn now(&self) -> Instant {
+        self.inner.data.lock().now
+    }
+
+    fn schedule_at(&mut self, deadline: Instant) -> Self::TimedSender {
+        VirtualTimeSender {
+            scheduler: self.clone(),
+            deadline,
+        }
+    }
+}
+
+/// Sender returned by [`VirtualTimeScheduler`], completes once the virtual clock reaches its
+/// deadline.
+pub struct VirtualTimeSender {
+    scheduler: VirtualTimeScheduler,
+    deadline: Instant,
+}
+
+impl Sender for VirtualTimeSender {
+    type Output = ();
+    type Error = crate::Error;
+    type Scheduler = VirtualTimeScheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        start_timer(&self.scheduler.inner, self.deadline, receiver);
+    }
+
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(self.scheduler.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VirtualTimeScheduler;
+    use crate::traits::{Scheduler, TimeScheduler};
+    use crate::SenderExt;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[test]
+    fn deterministic_order() {
+        let mut scheduler = VirtualTimeScheduler::new();
+        let start = scheduler.now();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        for (delay, name) in [(3, "c"), (1, "a"), (2, "b")] {
+            let order = order.clone();
+            let clock = scheduler.clone();
+            crate::start_detached(
+                scheduler
+                    .schedule_after(Duration::from_secs(delay))
+                    .map(move |_| order.lock().unwrap().push((name, clock.now() - start))),
+            );
+        }
+        scheduler.execute({
+            let order = order.clone();
+            move || order.lock().unwrap().push(("now", Duration::ZERO))
+        });
+
+        assert_eq!(scheduler.advance(Duration::from_millis(1500)), 2);
+        assert_eq!(scheduler.now() - start, Duration::from_millis(1500));
+        assert_eq!(scheduler.run_until_idle(), 2);
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec![
+                ("now", Duration::ZERO),
+                ("a", Duration::from_secs(1)),
+                ("b", Duration::from_secs(2)),
+                ("c", Duration::from_secs(3)),
+            ]
+        );
+    }
+}
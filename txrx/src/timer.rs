@@ -0,0 +1,418 @@
+//! A scheduler backed by a dedicated timer thread.
+//!
+//! See [`TimerScheduler`] for details.
+use crate::priv_sync::{Condvar, Mutex};
+use crate::stop_token::StopCallback;
+use crate::traits::{Channel, Receiver, Sender, TimeScheduler, Work};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::Instant;
+
+// Invoked with `true` when the deadline is reached, `false` if the timer is cancelled.
+pub(crate) type TimerWork = Box<dyn FnOnce(bool) + Send>;
+
+struct Entry {
+    deadline: Instant,
+    id: u64,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    // Reversed, so the max-heap pops the earliest deadline first. Equal deadlines run in the
+    // order they were added.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .deadline
+            .cmp(&self.deadline)
+            .then_with(|| other.id.cmp(&self.id))
+    }
+}
+
+/// Pending timers ordered by deadline, shared by the time schedulers in this crate.
+///
+/// Removed timers are left in the heap and skipped once they reach the top, so that removing a
+/// timer doesn't have to rebuild the heap.
+pub(crate) struct TimerQueue {
+    heap: BinaryHeap<Entry>,
+    // The work of the timers that haven't run or been removed yet.
+    work: HashMap<u64, TimerWork>,
+    next_id: u64,
+}
+
+impl TimerQueue {
+    pub fn new() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            work: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn push(&mut self, deadline: Instant, work: TimerWork) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.heap.push(Entry { deadline, id });
+        self.work.insert(id, work);
+        id
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<TimerWork> {
+        let work = self.work.remove(&id);
+        self.discard_removed();
+        work
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.heap.peek().map(|x| x.deadline)
+    }
+
+    /// Removes the earliest timer if its deadline is at or before `now`.
+    pub fn pop_due(&mut self, now: Instant) -> Option<(Instant, TimerWork)> {
+        match self.heap.peek() {
+            Some(entry) if entry.deadline <= now => {
+                let entry = self.heap.pop()?;
+                let work = self.work.remove(&entry.id)?;
+                self.discard_removed();
+                Some((entry.deadline, work))
+            }
+            _ => None,
+        }
+    }
+
+    // Pops removed timers off the top of the heap, so the top is always a pending timer.
+    fn discard_removed(&mut self) {
+        while matches!(self.heap.peek(), Some(entry) if !self.work.contains_key(&entry.id)) {
+            self.heap.pop();
+        }
+    }
+}
+
+/// The shared state of a time scheduler that owns a [`TimerQueue`].
+pub(crate) trait TimerHost: 'static + Send + Sync {
+    fn add(&self, deadline: Instant, work: TimerWork) -> u64;
+
+    /// Removes the timer with `id` and runs it as cancelled, if it hasn't run yet.
+    fn cancel(&self, id: u64);
+}
+
+/// Adds a timer that completes `receiver` at `deadline`, or cancels it as soon as the receiver
+/// requests stop.
+pub(crate) fn start_timer<H, R>(host: &Arc<H>, deadline: Instant, receiver: R)
+where
+    H: TimerHost,
+    R: 'static + Send + Receiver<Input = ()>,
+{
+    let token = receiver.get_stop_token();
+    // Holds the stop callback until the timer fires or is cancelled.
+    let callback_slot: Arc<Mutex<Option<StopCallback>>> = Arc::new(Mutex::new(None));
+    let id = host.add(deadline, {
+        let callback_slot = callback_slot.clone();
+        Box::new(move |fired| {
+            drop(callback_slot.lock().take());
+            if fired && !receiver.get_stop_token().stop_requested() {
+                receiver.set_value(());
+            } else {
+                receiver.set_cancelled();
+            }
+        })
+    });
+
+    if token.stop_possible() {
+        let weak: Weak<H> = Arc::downgrade(host);
+        let callback = token.on_stop(move || {
+            if let Some(host) = weak.upgrade() {
+                host.cancel(id);
+            }
+        });
+        *callback_slot.lock() = Some(callback);
+    }
+}
+
+struct Data {
+    queue: TimerQueue,
+    shutdown: bool,
+}
+
+struct Inner {
+    data: Mutex<Data>,
+    cond_var: Condvar,
+}
+
+impl TimerHost for Inner {
+    fn add(&self, deadline: Instant, work: TimerWork) -> u64 {
+        let id = self.data.lock().queue.push(deadline, work);
+        self.cond_var.notify_one();
+        id
+    }
+
+    fn cancel(&self, id: u64) {
+        let work = self.data.lock().queue.remove(id);
+        if let Some(work) = work {
+            work(false);
+        }
+    }
+}
+
+impl Inner {
+    fn run(&self) {
+        let mut data = self.data.lock();
+        loop {
+            let now = Instant::now();
+            if let Some((_, work)) = data.queue.pop_due(now) {
+                drop(data);
+                // Keep the thread alive for the other timers. The panic has already been
+                // reported by the panic hook.
+                let _ = std::panic::catch_unwind(AssertUnwindSafe(|| work(true)));
+                data = self.data.lock();
+                continue;
+            }
+            match data.queue.next_deadline() {
+                Some(deadline) => data = self.cond_var.wait_timeout(data, deadline - now),
+                None if data.shutdown => return,
+                None => data = self.cond_var.wait(data),
+            }
+        }
+    }
+}
+
+// Shuts the timer thread down once the last scheduler is dropped and all timers have fired.
+struct Handle {
+    inner: Arc<Inner>,
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        self.inner.data.lock().shutdown = true;
+        self.inner.cond_var.notify_one();
+    }
+}
+
+/// A [`TimeScheduler`] that runs work on a dedicated timer thread.
+///
+/// The thread is started by [`new()`](TimerScheduler::new) and exits once all clones of the
+/// scheduler, and all senders created from it, have been dropped and all pending timers have
+/// fired. Work runs on the timer thread, so it should be short or be transferred to another
+/// scheduler.
+///
+/// Timers are cancelled as soon as stop is requested on the receiver they are started with.
+/// The receiver is then cancelled on the thread that requested stop.
+///
+/// A panic in work running on the timer thread is caught, so that the other timers still fire.
+#[derive(Clone)]
+pub struct TimerScheduler {
+    handle: Arc<Handle>,
+}
+
+impl Default for TimerScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns a scheduler for the process-wide timer thread, started on first use.
+///
+/// This is the timer used by [`timeout()`](crate::SenderExt::timeout).
+pub fn global() -> TimerScheduler {
+    static GLOBAL: OnceLock<TimerScheduler> = OnceLock::new();
+    GLOBAL.get_or_init(TimerScheduler::new).clone()
+}
+
+impl TimerScheduler {
+    pub fn new() -> Self {
+        let inner = Arc::new(Inner {
+            data: Mutex::new(Data {
+                queue: TimerQueue::new(),
+                shutdown: false,
+            }),
+            cond_var: Condvar::new(),
+        });
+        let thread_inner = inner.clone();
+        std::thread::Builder::new()
+            .name("txrx-timer".into())
+            .spawn(move || thread_inner.run())
+            .expect("Failed to spawn timer thread");
+        Self {
+            handle: Arc::new(Handle { inner }),
+        }
+    }
+}
+
+impl crate::traits::Scheduler for TimerScheduler {
+    type Sender = TimerSender;
+
+    fn schedule(&mut self) -> Self::Sender {
+        self.schedule_at(Instant::now())
+    }
+
+    fn execute<W>(&mut self, work: W)
+    where
+        W: 'static + Send + Work,
+    {
+        self.handle.inner.add(
+            Instant::now(),
+            Box::new(move |_| {
+                work.execute();
+            }),
+        );
+    }
+}
+
+impl TimeScheduler for TimerScheduler {
+    type TimedSender = TimerSender;
+
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn schedule_at(&mut self, deadline: Instant) -> Self::TimedSender {
+        TimerSender {
+            scheduler: self.clone(),
+            deadline,
+        }
+    }
+}
+
+/// Sender returned by [`TimerScheduler`], completes once its deadline is reached.
+pub struct TimerSender {
+    scheduler: TimerScheduler,
+    deadline: Instant,
+}
+
+impl Sender for TimerSender {
+    type Output = ();
+    type Error = crate::Error;
+    type Scheduler = TimerScheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        start_timer(&self.scheduler.handle.inner, self.deadline, receiver);
+    }
+
+    // Cancellation completes inline on the thread that requested stop.
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        match channel {
+            Channel::Cancelled => None,
+            _ => Some(self.scheduler.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TimerQueue, TimerScheduler};
+    use crate::stop_token::StopSource;
+    use crate::traits::{Channel, Receiver, Scheduler, Sender, TimeScheduler};
+    use crate::SenderExt;
+    use std::sync::mpsc::{channel, Sender as ChannelSender};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn ordering() {
+        let mut scheduler = TimerScheduler::new();
+        let now = scheduler.now();
+        let late = scheduler
+            .schedule_at(now + Duration::from_millis(20))
+            .map(|_| Instant::now())
+            .ensure_started();
+        let early = scheduler
+            .schedule_after(Duration::from_millis(5))
+            .map(|_| Instant::now())
+            .ensure_started();
+        let (late, early) = late.when_both(early).sync_wait().unwrap();
+        assert!(early < late);
+        assert!(late >= now + Duration::from_millis(20));
+    }
+
+    struct StoppableReceiver {
+        source: StopSource,
+        result: ChannelSender<bool>,
+    }
+
+    impl Receiver for StoppableReceiver {
+        type Input = ();
+        type Error = crate::Error;
+
+        fn set_value(self, _value: Self::Input) {
+            self.result.send(true).unwrap();
+        }
+
+        fn set_error(self, _error: Self::Error) {}
+
+        fn set_cancelled(self) {
+            self.result.send(false).unwrap();
+        }
+
+        fn get_stop_token(&self) -> crate::stop_token::StopToken {
+            self.source.token()
+        }
+    }
+
+    #[test]
+    fn stop_cancels_timer() {
+        let mut scheduler = TimerScheduler::new();
+        let source = StopSource::new();
+        let (tx, rx) = channel();
+        scheduler
+            .schedule_after(Duration::from_secs(60))
+            .start(StoppableReceiver {
+                source: source.clone(),
+                result: tx,
+            });
+        source.request_stop();
+        assert!(!rx.recv_timeout(Duration::from_secs(5)).unwrap());
+    }
+
+    #[test]
+    fn removed_timers_are_skipped() {
+        let now = Instant::now();
+        let mut queue = TimerQueue::new();
+        let first = queue.push(now, Box::new(|_| {}));
+        let second = queue.push(now + Duration::from_millis(1), Box::new(|_| {}));
+        queue.push(now + Duration::from_millis(2), Box::new(|_| {}));
+        assert!(queue.remove(second).is_some());
+        assert!(queue.remove(first).is_some());
+        assert!(queue.remove(first).is_none());
+        assert_eq!(queue.next_deadline(), Some(now + Duration::from_millis(2)));
+        let (deadline, _) = queue.pop_due(now + Duration::from_secs(1)).unwrap();
+        assert_eq!(deadline, now + Duration::from_millis(2));
+        assert!(queue.next_deadline().is_none());
+    }
+
+    #[test]
+    fn panic_keeps_timer_running() {
+        let mut scheduler = TimerScheduler::new();
+        scheduler.execute(|| panic!("Timer work panicked"));
+        let result = scheduler
+            .schedule_after(Duration::from_millis(5))
+            .map(|_| 1)
+            .sync_wait();
+        assert_eq!(result.unwrap(), 1);
+    }
+
+    #[test]
+    fn cancellation_has_no_completion_scheduler() {
+        let mut scheduler = TimerScheduler::new();
+        let sender = scheduler.schedule();
+        assert!(sender.get_completion_scheduler(Channel::Value).is_some());
+        assert!(sender
+            .get_completion_scheduler(Channel::Cancelled)
+            .is_none());
+    }
+}
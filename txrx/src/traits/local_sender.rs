@@ -0,0 +1,110 @@
+use crate::local::and_then::AndThen;
+use crate::local::map::Map;
+use crate::local::when_both::WhenBoth;
+use crate::traits::{Channel, Receiver, Work};
+use core::marker::PhantomData;
+
+/// A sender that is started and completed on a single thread.
+///
+/// This is the same as [`Sender`](crate::traits::Sender), except that neither the sender, its
+/// output nor the receivers it is started with have to be `Send`. See [`local`](crate::local) for
+/// details.
+pub trait LocalSender {
+    type Output: 'static;
+    type Error: 'static;
+    type Scheduler: 'static + Clone + LocalScheduler;
+
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Receiver<Input = Self::Output, Error = Self::Error>;
+
+    /// Returns the scheduler that the receiver will be completed on for `channel`, if known.
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        None
+    }
+}
+
+/// A scheduler for work that must stay on the current thread.
+pub trait LocalScheduler: 'static + Clone {
+    type Sender: 'static + LocalSender<Output = ()>;
+    fn schedule(&mut self) -> Self::Sender;
+    fn execute<W>(&mut self, work: W)
+    where
+        W: 'static + Work,
+    {
+        self.schedule()
+            .start(LocalExecuteReceiver::<Self::Sender, W>::new(work));
+    }
+}
+
+struct LocalExecuteReceiver<S, W> {
+    _phantom: PhantomData<S>,
+    work: W,
+}
+
+impl<S, W> LocalExecuteReceiver<S, W> {
+    fn new(work: W) -> Self {
+        Self {
+            _phantom: PhantomData,
+            work,
+        }
+    }
+}
+
+impl<S: LocalSender, W: Work> Receiver for LocalExecuteReceiver<S, W> {
+    type Input = S::Output;
+    type Error = S::Error;
+
+    fn set_value(self, _value: Self::Input) {
+        self.work.execute();
+    }
+
+    fn set_error(self, _error: Self::Error) {
+        self.work.cancel();
+    }
+
+    fn set_cancelled(self) {
+        self.work.cancel();
+    }
+}
+
+mod sealed {
+    use crate::traits::LocalSender;
+
+    pub trait Sealed {}
+
+    impl<T: LocalSender> Sealed for T {}
+}
+
+/// Adaptors for [`LocalSender`]s.
+pub trait LocalSenderExt: 'static + sealed::Sealed + LocalSender + Sized {
+    #[inline]
+    fn map<F, Ret>(self, func: F) -> Map<Self, F>
+    where
+        F: FnOnce(Self::Output) -> Ret,
+    {
+        Map::new(self, func)
+    }
+
+    /// Returns a sender that invokes `func` with the value sent by `self`, and then starts the
+    /// local sender returned by `func`.
+    #[inline]
+    fn and_then<Func, Next>(self, func: Func) -> AndThen<Self, Func>
+    where
+        Func: FnOnce(Self::Output) -> Next,
+        Next: LocalSender,
+    {
+        AndThen::new(self, func)
+    }
+
+    /// Returns a sender that completes when both `self` and `rhs` completes.
+    ///
+    /// The receiver is completed on whatever thread context the last of the two senders completes
+    /// on, which for local senders is always the current thread.
+    #[inline]
+    fn when_both<Rhs>(self, rhs: Rhs) -> WhenBoth<Self, Rhs> {
+        WhenBoth::new(self, rhs)
+    }
+}
+
+impl<T: 'static + LocalSender> LocalSenderExt for T {}
@@ -1,9 +1,17 @@
+pub use local_sender::{LocalScheduler, LocalSender, LocalSenderExt};
+pub use operation_state::{Connect, FromSender, OperationState, SenderOperation};
 pub use receiver::Receiver;
-pub use scheduler::{Scheduler, Work};
-pub use sender::Sender;
+pub use scheduler::{EitherScheduleSender, Scheduler, Work};
+pub use sender::{Channel, Sender};
 pub use sender_ext::SenderExt;
+#[cfg(feature = "std")]
+pub use time_scheduler::TimeScheduler;
 
+mod local_sender;
+mod operation_state;
 pub mod receiver;
 mod scheduler;
 mod sender;
 mod sender_ext;
+#[cfg(feature = "std")]
+mod time_scheduler;
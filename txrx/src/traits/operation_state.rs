@@ -0,0 +1,279 @@
+use crate::traits::{Channel, Receiver, Sender};
+use core::pin::Pin;
+
+/// The state of a sender that has been connected to a receiver.
+///
+/// The operation state owns everything needed to run the work, so it can be placed inline in
+/// another operation state or on the stack. It must not be moved once started, which is why
+/// `start()` takes a pinned reference.
+pub trait OperationState {
+    /// Starts the work.
+    ///
+    /// # Safety
+    ///
+    /// Once started, the operation state must not be dropped until its receiver has been
+    /// completed or dropped. Operation states of adaptors store the operation states of their
+    /// input senders inline and hand out receivers that point into them.
+    unsafe fn start(self: Pin<&mut Self>);
+}
+
+/// Connects a sender to a receiver, producing an [`OperationState`].
+///
+/// This is a separate step from starting the work, which allows senders to lay out all state
+/// for a pipeline in a single operation state instead of allocating per stage.
+///
+/// [`just()`](crate::just), [`map()`](crate::SenderExt::map),
+/// [`and_then()`](crate::SenderExt::and_then) and [`when_both()`](crate::SenderExt::when_both)
+/// implement `Connect` without allocating. Other senders are connected by wrapping them in
+/// [`FromSender`], which calls [`Sender::start()`] once the operation is started.
+///
+/// ## Examples
+///
+/// ```
+/// use txrx::traits::{Connect, OperationState, Receiver};
+/// use std::pin::Pin;
+///
+/// struct PrintReceiver;
+///
+/// impl Receiver for PrintReceiver {
+///     type Input = i32;
+///     type Error = txrx::Error;
+///
+///     fn set_value(self, value: Self::Input) {
+///         println!("Got {}", value);
+///     }
+///
+///     fn set_error(self, _error: Self::Error) {}
+///
+///     fn set_cancelled(self) {}
+/// }
+///
+/// let mut operation = txrx::just(10).connect(PrintReceiver);
+/// // Safety: the operation state outlives the receiver, which is completed right away.
+/// unsafe { Pin::new(&mut operation).start() };
+/// ```
+pub trait Connect<R> {
+    type Operation: OperationState;
+
+    fn connect(self, receiver: R) -> Self::Operation;
+}
+
+/// Bridges a [`Sender`] to [`Connect`].
+///
+/// Senders that don't implement `Connect` themselves can be wrapped in `FromSender` to be used in
+/// a pipeline that is connected. The sender is started with [`Sender::start()`], so it allocates
+/// the same as when it isn't connected.
+///
+/// ## Examples
+///
+/// ```
+/// use txrx::manual_executor::ManualExecutor;
+/// use txrx::traits::{Connect, FromSender, OperationState, Receiver, Scheduler};
+/// use txrx::SenderExt;
+/// use std::pin::Pin;
+///
+/// struct PrintReceiver;
+///
+/// impl Receiver for PrintReceiver {
+///     type Input = i32;
+///     type Error = txrx::Error;
+///
+///     fn set_value(self, value: Self::Input) {
+///         println!("Got {}", value);
+///     }
+///
+///     fn set_error(self, _error: Self::Error) {}
+///
+///     fn set_cancelled(self) {}
+/// }
+///
+/// let executor = ManualExecutor::new();
+/// let sender = FromSender::new(executor.scheduler().schedule()).map(|_| 10);
+/// let mut operation = sender.connect(PrintReceiver);
+/// // Safety: the operation state outlives the executor.
+/// unsafe { Pin::new(&mut operation).start() };
+/// executor.runner().run_one();
+/// ```
+pub struct FromSender<S> {
+    sender: S,
+}
+
+impl<S> FromSender<S> {
+    #[inline]
+    pub fn new(sender: S) -> Self {
+        Self { sender }
+    }
+}
+
+impl<S: Sender> Sender for FromSender<S> {
+    type Output = S::Output;
+    type Error = S::Error;
+    type Scheduler = S::Scheduler;
+
+    #[inline]
+    fn start<R>(self, receiver: R)
+    where
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        self.sender.start(receiver);
+    }
+
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        self.sender.get_completion_scheduler(channel)
+    }
+}
+
+impl<S, R> Connect<R> for FromSender<S>
+where
+    S: Sender,
+    R: 'static + Send + Receiver<Input = S::Output, Error = S::Error>,
+{
+    type Operation = SenderOperation<S, R>;
+
+    #[inline]
+    fn connect(self, receiver: R) -> Self::Operation {
+        SenderOperation::new(self.sender, receiver)
+    }
+}
+
+/// Operation state bridging a [`Sender`] to [`Connect`], see [`FromSender`].
+pub struct SenderOperation<S, R> {
+    sender_and_receiver: Option<(S, R)>,
+}
+
+impl<S, R> SenderOperation<S, R> {
+    #[inline]
+    pub fn new(sender: S, receiver: R) -> Self {
+        Self {
+            sender_and_receiver: Some((sender, receiver)),
+        }
+    }
+}
+
+// Neither the sender nor the receiver is pinned, they are moved out when started.
+impl<S, R> Unpin for SenderOperation<S, R> {}
+
+impl<S, R> OperationState for SenderOperation<S, R>
+where
+    S: Sender,
+    R: 'static + Send + Receiver<Input = S::Output, Error = S::Error>,
+{
+    #[inline]
+    unsafe fn start(self: Pin<&mut Self>) {
+        let (sender, receiver) = self
+            .get_mut()
+            .sender_and_receiver
+            .take()
+            .expect("Operation already started");
+        sender.start(receiver);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test::ManualSender;
+    use crate::traits::{Connect, FromSender, OperationState, Receiver};
+    use crate::SenderExt;
+    use std::marker::PhantomPinned;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    struct FlagReceiver(Arc<AtomicBool>);
+
+    impl Receiver for FlagReceiver {
+        type Input = ();
+        type Error = crate::Error;
+
+        fn set_value(self, _value: Self::Input) {
+            self.0.store(true, Ordering::Release);
+        }
+
+        fn set_error(self, _error: Self::Error) {}
+
+        fn set_cancelled(self) {}
+    }
+
+    struct SlotReceiver<T>(Arc<std::sync::Mutex<Option<T>>>);
+
+    impl<T: Send> Receiver for SlotReceiver<T> {
+        type Input = T;
+        type Error = crate::Error;
+
+        fn set_value(self, value: Self::Input) {
+            *self.0.lock().unwrap() = Some(value);
+        }
+
+        fn set_error(self, _error: Self::Error) {}
+
+        fn set_cancelled(self) {}
+    }
+
+    // A sender that only supports connect, with an operation state that must stay pinned.
+    struct ConnectOnly;
+
+    struct ConnectOnlyOperation<R> {
+        receiver: Option<R>,
+        _pinned: PhantomPinned,
+    }
+
+    impl<R: Receiver<Input = ()>> OperationState for ConnectOnlyOperation<R> {
+        unsafe fn start(self: Pin<&mut Self>) {
+            // Safety: receiver is never structurally pinned.
+            let me = self.get_unchecked_mut();
+            if let Some(receiver) = me.receiver.take() {
+                receiver.set_value(());
+            }
+        }
+    }
+
+    impl<R: Receiver<Input = ()>> Connect<R> for ConnectOnly {
+        type Operation = ConnectOnlyOperation<R>;
+
+        fn connect(self, receiver: R) -> Self::Operation {
+            ConnectOnlyOperation {
+                receiver: Some(receiver),
+                _pinned: PhantomPinned,
+            }
+        }
+    }
+
+    #[test]
+    fn sender_bridge() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let (sender, trigger) = ManualSender::new();
+        let mut operation = FromSender::new(sender).connect(FlagReceiver(flag.clone()));
+        trigger.trigger();
+        assert!(!flag.load(Ordering::Acquire));
+        unsafe { Pin::new(&mut operation).start() };
+        assert!(!flag.load(Ordering::Acquire));
+        trigger.trigger();
+        assert!(flag.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn connect_only() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let operation = ConnectOnly.connect(FlagReceiver(flag.clone()));
+        let mut operation = Box::pin(operation);
+        unsafe { operation.as_mut().start() };
+        assert!(flag.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn connected_pipeline_does_not_allocate() {
+        let slot = Arc::new(std::sync::Mutex::new(None));
+        let receiver = SlotReceiver(slot.clone());
+        let ((), allocations) = crate::test::allocations(|| {
+            let mut operation = crate::just(1)
+                .map(|x| x + 1)
+                .and_then(|x| crate::just(x * 10))
+                .when_both(crate::just(3).map(|x| x * 2))
+                .connect(receiver);
+            // Safety: every sender completes inline.
+            unsafe { Pin::new_unchecked(&mut operation).start() };
+        });
+        assert_eq!(allocations, 0);
+        assert_eq!(slot.lock().unwrap().take(), Some((20, 6)));
+    }
+}
@@ -1,31 +1,95 @@
+use crate::env::Env;
+use crate::stop_token::StopToken;
+use alloc::boxed::Box;
+
 pub trait Receiver {
     type Input;
+    type Error;
 
     fn set_value(self, value: Self::Input);
-    fn set_error(self, error: crate::Error);
+    fn set_error(self, error: Self::Error);
     fn set_cancelled(self);
+
+    /// Returns a token that senders can use to check if the receiver wants the work to stop.
+    ///
+    /// Senders that honour stop requests complete with `set_cancelled()` once stop is requested.
+    /// The default implementation returns a token that is never stopped.
+    fn get_stop_token(&self) -> StopToken {
+        StopToken::never()
+    }
+
+    /// Returns the environment of the receiver, see [`env`](crate::env) for details.
+    ///
+    /// Adaptors forward the environment of the receiver they are connected to. The default
+    /// implementation returns an empty environment.
+    fn get_env(&self) -> Env {
+        Env::new()
+    }
 }
 
 pub trait DynReceiver {
     type Input;
+    type Error;
 
     fn dyn_set_value(&mut self, value: Self::Input);
-    fn dyn_set_error(&mut self, error: crate::Error);
+    fn dyn_set_error(&mut self, error: Self::Error);
     fn dyn_set_cancelled(&mut self);
+
+    fn dyn_get_stop_token(&self) -> StopToken {
+        StopToken::never()
+    }
+
+    fn dyn_get_env(&self) -> Env {
+        Env::new()
+    }
+}
+
+impl<T: ?Sized + DynReceiver> DynReceiver for Box<T> {
+    type Input = T::Input;
+    type Error = T::Error;
+
+    fn dyn_set_value(&mut self, value: Self::Input) {
+        (**self).dyn_set_value(value);
+    }
+
+    fn dyn_set_error(&mut self, error: Self::Error) {
+        (**self).dyn_set_error(error);
+    }
+
+    fn dyn_set_cancelled(&mut self) {
+        (**self).dyn_set_cancelled();
+    }
+
+    fn dyn_get_stop_token(&self) -> StopToken {
+        (**self).dyn_get_stop_token()
+    }
+
+    fn dyn_get_env(&self) -> Env {
+        (**self).dyn_get_env()
+    }
 }
 
 impl<T: DynReceiver> Receiver for T {
     type Input = <Self as DynReceiver>::Input;
+    type Error = <Self as DynReceiver>::Error;
 
     fn set_value(mut self, value: Self::Input) {
         self.dyn_set_value(value);
     }
 
-    fn set_error(mut self, error: crate::Error) {
+    fn set_error(mut self, error: Self::Error) {
         self.dyn_set_error(error);
     }
 
     fn set_cancelled(mut self) {
         self.dyn_set_cancelled();
     }
+
+    fn get_stop_token(&self) -> StopToken {
+        self.dyn_get_stop_token()
+    }
+
+    fn get_env(&self) -> Env {
+        self.dyn_get_env()
+    }
 }
@@ -1,5 +1,6 @@
-use crate::traits::{Receiver, Sender};
-use std::marker::PhantomData;
+use crate::traits::{Channel, Receiver, Sender};
+use core::marker::PhantomData;
+use either::Either;
 
 pub trait Scheduler: 'static + Send + Clone {
     type Sender: 'static + Send + Sender<Output = ()>;
@@ -15,6 +16,15 @@ pub trait Scheduler: 'static + Send + Clone {
 
 pub trait Work {
     fn execute(self);
+
+    /// Invoked instead of [`execute()`](Work::execute) if the scheduler drops the work without
+    /// running it, for instance because it has been shut down. Work that carries a receiver
+    /// should complete it as cancelled.
+    fn cancel(self)
+    where
+        Self: Sized,
+    {
+    }
 }
 
 impl<T: FnOnce()> Work for T {
@@ -39,12 +49,67 @@ impl<S, W> ExecuteReceiver<S, W> {
 
 impl<S: Sender, W: Work> Receiver for ExecuteReceiver<S, W> {
     type Input = S::Output;
+    type Error = S::Error;
 
     fn set_value(self, _value: Self::Input) {
         self.work.execute();
     }
 
-    fn set_error(self, _error: crate::Error) {}
+    fn set_error(self, _error: Self::Error) {
+        self.work.cancel();
+    }
+
+    fn set_cancelled(self) {
+        self.work.cancel();
+    }
+}
+
+/// Schedules work on either the left or the right scheduler.
+///
+/// This is the completion scheduler of an `Either` sender.
+impl<L: Scheduler, R: Scheduler> Scheduler for Either<L, R> {
+    type Sender = EitherScheduleSender<L, R>;
+
+    fn schedule(&mut self) -> Self::Sender {
+        EitherScheduleSender {
+            scheduler: self.clone(),
+        }
+    }
+
+    fn execute<W>(&mut self, work: W)
+    where
+        W: 'static + Send + Work,
+    {
+        match self {
+            Either::Left(left) => left.execute(work),
+            Either::Right(right) => right.execute(work),
+        }
+    }
+}
+
+pub struct EitherScheduleSender<L, R> {
+    scheduler: Either<L, R>,
+}
+
+impl<L: Scheduler, R: Scheduler> Sender for EitherScheduleSender<L, R> {
+    type Output = ();
+    type Error = crate::Error;
+    type Scheduler = Either<L, R>;
 
-    fn set_cancelled(self) {}
+    fn start<Recv>(mut self, receiver: Recv)
+    where
+        Recv: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
+    {
+        self.scheduler.execute(move || {
+            if receiver.get_stop_token().stop_requested() {
+                receiver.set_cancelled();
+            } else {
+                receiver.set_value(());
+            }
+        });
+    }
+
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        Some(self.scheduler.clone())
+    }
 }
@@ -1,20 +1,39 @@
 use crate::traits::Receiver;
-use crate::{ImmediateScheduler, SenderExt};
+use crate::SenderExt;
 use either::Either;
 
+/// The completion channels of a sender.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Channel {
+    Value,
+    Error,
+    Cancelled,
+}
+
 pub trait Sender {
     type Output: 'static + Send;
+    type Error: 'static + Send;
     type Scheduler: 'static + Clone + Send + crate::traits::Scheduler;
 
     fn start<R>(self, receiver: R)
     where
-        R: 'static + Send + Receiver<Input = Self::Output>;
+        R: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>;
 
-    fn get_scheduler(&self) -> Self::Scheduler;
+    /// Returns the scheduler that the receiver will be completed on for `channel`.
+    ///
+    /// Returns `None` if the scheduler isn't known. This is the case for senders that complete
+    /// inline on whatever execution context they are started on, and the default implementation
+    /// always returns `None`.
+    fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+        None
+    }
 }
 
 /// Implements `Sender` for Either.
 ///
+/// The error type is the error type of the left sender, errors from the right sender are converted
+/// using `Into`. The completion scheduler is the completion scheduler of the active branch.
+///
 /// ## Examples
 ///
 /// Basic usage
@@ -37,13 +56,15 @@ impl<L, R> Sender for Either<L, R>
 where
     L: 'static + Sender,
     R: 'static + Sender,
+    R::Error: Into<L::Error>,
 {
     type Output = Either<L::Output, R::Output>;
-    type Scheduler = ImmediateScheduler;
+    type Error = L::Error;
+    type Scheduler = Either<L::Scheduler, R::Scheduler>;
 
     fn start<Recv>(self, receiver: Recv)
     where
-        Recv: 'static + Send + Receiver<Input = Self::Output>,
+        Recv: 'static + Send + Receiver<Input = Self::Output, Error = Self::Error>,
     {
         match self {
             Either::Left(left) => left
@@ -51,18 +72,23 @@ where
                 .start(receiver),
             Either::Right(right) => right
                 .map(|v| -> Self::Output { Either::Right(v) })
+                .map_error(Into::into)
                 .start(receiver),
         }
     }
 
-    fn get_scheduler(&self) -> Self::Scheduler {
-        ImmediateScheduler
+    fn get_completion_scheduler(&self, channel: Channel) -> Option<Self::Scheduler> {
+        match self {
+            Either::Left(left) => left.get_completion_scheduler(channel).map(Either::Left),
+            Either::Right(right) => right.get_completion_scheduler(channel).map(Either::Right),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::traits::Sender;
+    use crate::manual_executor::ManualExecutor;
+    use crate::traits::{Channel, Scheduler, Sender};
     use crate::SenderExt;
     use either::Either;
 
@@ -87,4 +113,22 @@ mod tests {
             "hello"
         );
     }
+
+    #[test]
+    fn either_completion_scheduler() {
+        let exec = ManualExecutor::new();
+        let left: Either<_, crate::factories::just_sender::Just<()>> =
+            Either::Left(exec.scheduler().schedule());
+        assert!(left.get_completion_scheduler(Channel::Value).is_some());
+        let fut = left.bulk(2, |_, _| ()).ensure_started();
+        assert!(!fut.is_complete());
+        assert!(exec.runner().run_one());
+        assert!(!fut.is_complete());
+        assert!(exec.runner().run_one());
+        assert!(fut.is_complete());
+
+        let right: Either<crate::manual_executor::ScheduledSender, _> =
+            Either::Right(crate::just(()));
+        assert!(right.get_completion_scheduler(Channel::Value).is_none());
+    }
 }
@@ -1,11 +1,24 @@
 use crate::adaptors::and_then::AndThen;
 use crate::adaptors::bulk::Bulk;
+#[cfg(feature = "std")]
+use crate::adaptors::catch_unwind::CatchUnwind;
 use crate::adaptors::ensure_started::EnsureStarted;
+use crate::adaptors::let_cancelled::LetCancelled;
+use crate::adaptors::let_error::LetError;
 use crate::adaptors::map::Map;
+use crate::adaptors::map_error::MapError;
+use crate::adaptors::split::Split;
+#[cfg(feature = "std")]
+use crate::adaptors::timeout::{TimedOut, Timeout};
 use crate::adaptors::transfer::Transfer;
+use crate::adaptors::upon_cancelled::UponCancelled;
+use crate::adaptors::upon_error::UponError;
+use crate::adaptors::when_any::WhenAny;
 use crate::adaptors::when_both::WhenBoth;
+use crate::adaptors::with_env::WithEnv;
 use crate::consumers::into_awaitable::Awaitable;
 use crate::traits::Sender;
+use crate::AnySender;
 
 mod sealed {
     use crate::traits::Sender;
@@ -23,15 +36,175 @@ pub trait SenderExt: 'static + sealed::Sealed + Sender + Sized {
         Map::new(self, func)
     }
 
-    fn sync_wait(self) -> crate::consumers::sync_wait::WaitResult<Self::Output> {
+    /// Returns a sender that transforms the error sent by `self` using `func`.
+    ///
+    /// Values and cancellation are forwarded unchanged.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use txrx::SenderExt;
+    /// let error = txrx::factories::error(10)
+    ///     .map_error(|x| x * 2)
+    ///     .sync_wait()
+    ///     .unwrap_error();
+    ///
+    /// assert_eq!(error, 20);
+    /// ```
+    #[inline]
+    fn map_error<F, Ret>(self, func: F) -> MapError<Self, F>
+    where
+        F: FnOnce(Self::Error) -> Ret,
+    {
+        MapError::new(self, func)
+    }
+
+    /// Returns a sender that sends the value returned by `func` if `self` sends an error.
+    ///
+    /// Values and cancellation are forwarded unchanged.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use txrx::SenderExt;
+    /// let value = txrx::factories::error(10)
+    ///     .map(|_: ()| 0)
+    ///     .upon_error(|x| x * 2)
+    ///     .sync_wait()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(value, 20);
+    /// ```
+    #[inline]
+    fn upon_error<F>(self, func: F) -> UponError<Self, F>
+    where
+        F: FnOnce(Self::Error) -> Self::Output,
+    {
+        UponError::new(self, func)
+    }
+
+    /// Returns a sender that sends the value returned by `func` if `self` is cancelled.
+    ///
+    /// Values and errors are forwarded unchanged.
+    #[inline]
+    fn upon_cancelled<F>(self, func: F) -> UponCancelled<Self, F>
+    where
+        F: FnOnce() -> Self::Output,
+    {
+        UponCancelled::new(self, func)
+    }
+
+    /// Returns a sender that invokes `func` with the error sent by `self`, and then starts the
+    /// sender returned by `func`.
+    ///
+    /// This is the error channel counterpart of [`and_then()`](SenderExt::and_then). Errors from
+    /// the returned sender are converted using `Into`.
+    #[inline]
+    fn let_error<F, Next>(self, func: F) -> LetError<Self, F>
+    where
+        F: FnOnce(Self::Error) -> Next,
+        Next: Sender<Output = Self::Output>,
+    {
+        LetError::new(self, func)
+    }
+
+    /// Returns a sender that starts the sender returned by `func` if `self` is cancelled.
+    ///
+    /// Errors from the returned sender are converted using `Into`.
+    #[inline]
+    fn let_cancelled<F, Next>(self, func: F) -> LetCancelled<Self, F>
+    where
+        F: FnOnce() -> Next,
+        Next: Sender<Output = Self::Output>,
+    {
+        LetCancelled::new(self, func)
+    }
+
+    #[cfg(feature = "std")]
+    fn sync_wait(self) -> crate::consumers::sync_wait::WaitResult<Self::Output, Self::Error> {
         crate::sync_wait(self)
     }
 
+    /// Starts `self` right away and returns a sender that completes with its result.
+    ///
+    /// Since `self` is started before a receiver is attached, it sees an empty
+    /// [`env`](crate::env). Stop requests from the attached receiver are forwarded.
     #[inline]
     fn ensure_started(self) -> EnsureStarted<Self> {
         EnsureStarted::new(self)
     }
 
+    /// Returns a cloneable sender that sends the result of `self` to every receiver it is started
+    /// with.
+    ///
+    /// `self` is started the first time any of the clones is started, later receivers are
+    /// completed with the stored result. Values are cloned for each receiver, errors are shared
+    /// through an `Arc`. The input sender is never stopped early, since other receivers may still
+    /// be interested in the result. If `self` drops its receiver without completing it, all
+    /// receivers are cancelled.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use txrx::SenderExt;
+    /// let split = txrx::factories::just(10).split();
+    /// let doubled = split.clone().map(|x| x * 2);
+    /// let tripled = split.map(|x| x * 3);
+    ///
+    /// assert_eq!(doubled.when_both(tripled).sync_wait().unwrap(), (20, 30));
+    /// ```
+    #[inline]
+    fn split(self) -> Split<Self>
+    where
+        Self::Output: Clone,
+    {
+        Split::new(self)
+    }
+
+    /// Returns a sender that completes with a [`TimedOut`] error if `self` hasn't completed
+    /// within `duration`.
+    ///
+    /// Stop is requested on `self` when the timeout expires. Use
+    /// [`cancel_on_timeout()`](Timeout::cancel_on_timeout) to cancel the receiver instead of
+    /// sending an error. The timeout runs on the [global timer](crate::timer::global), use
+    /// [`timeout_on()`](SenderExt::timeout_on) to select another time scheduler.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use txrx::adaptors::timeout::TimedOut;
+    /// use txrx::SenderExt;
+    ///
+    /// let error = txrx::factories::from_future(txrx::ImmediateScheduler, std::future::pending::<()>())
+    ///     .timeout(Duration::from_millis(10))
+    ///     .sync_wait()
+    ///     .unwrap_error();
+    /// assert!(error.downcast_ref::<TimedOut>().is_some());
+    /// ```
+    #[cfg(feature = "std")]
+    #[inline]
+    fn timeout(self, duration: core::time::Duration) -> Timeout<Self, crate::timer::TimerScheduler>
+    where
+        Self::Error: From<TimedOut>,
+    {
+        Timeout::new(self, crate::timer::global(), duration)
+    }
+
+    /// Same as [`timeout()`](SenderExt::timeout), but measures the timeout using `scheduler`.
+    #[cfg(feature = "std")]
+    #[inline]
+    fn timeout_on<Sched>(
+        self,
+        scheduler: Sched,
+        duration: core::time::Duration,
+    ) -> Timeout<Self, Sched>
+    where
+        Sched: crate::traits::TimeScheduler,
+    {
+        Timeout::new(self, scheduler, duration)
+    }
+
     #[inline]
     fn transfer<Sched>(self, scheduler: Sched) -> Transfer<Self, Sched> {
         Transfer::new(self, scheduler)
@@ -40,6 +213,7 @@ pub trait SenderExt: 'static + sealed::Sealed + Sender + Sized {
     /// Returns a sender that completes when both the `self` sender and the `rhs` sender completes.
     ///
     /// The output type of the sender is `(Self::Output, Right::Output)`. Scheduler is `Self::Scheduler`.
+    /// The error type is `Self::Error`, errors from `rhs` are converted using `Into`.
     ///
     /// ## Example
     ///
@@ -57,6 +231,38 @@ pub trait SenderExt: 'static + sealed::Sealed + Sender + Sized {
         WhenBoth::new(self, rhs)
     }
 
+    /// Returns a sender that completes with whichever of `self` and `rhs` first sends a value or
+    /// an error.
+    ///
+    /// The output is `Either::Left` with the value of `self`, or `Either::Right` with the value of
+    /// `rhs`. Stop is requested on the losing sender, and the receiver is only cancelled if both
+    /// senders are cancelled. Errors from `rhs` are converted using `Into`.
+    ///
+    /// See [`when_any_iter()`](crate::when_any_iter) for racing more than two senders.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use txrx::SenderExt;
+    /// use either::Either;
+    ///
+    /// let value = txrx::factories::cancelled()
+    ///     .map(|_| 10)
+    ///     .when_any(txrx::factories::just("hello"))
+    ///     .sync_wait()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(value, Either::Right("hello"));
+    /// ```
+    #[inline]
+    fn when_any<Rhs>(self, rhs: Rhs) -> WhenAny<Self, Rhs> {
+        WhenAny::new(self, rhs)
+    }
+
+    /// Returns a sender that invokes `func` with the value sent by `self`, and then starts the
+    /// sender returned by `func`.
+    ///
+    /// The error type is `Self::Error`, errors from the returned sender are converted using `Into`.
     #[inline]
     fn and_then<Func>(self, func: Func) -> AndThen<Self, Func> {
         AndThen::new(self, func)
@@ -83,7 +289,7 @@ pub trait SenderExt: 'static + sealed::Sealed + Sender + Sized {
     /// ## Examples
     ///
     /// ```
-    /// use txrx::traits::{Scheduler, Sender, Receiver, SenderExt};
+    /// use txrx::traits::{Channel, Scheduler, Sender, Receiver, SenderExt};
     ///
     /// // This is a scheduler that always spins up a new thread to start its work on.
     /// #[derive(Copy, Clone)]
@@ -91,17 +297,18 @@ pub trait SenderExt: 'static + sealed::Sealed + Sender + Sized {
     ///
     /// impl Sender for NewThreadScheduler {
     ///     type Output = ();
+    ///     type Error = txrx::Error;
     ///     type Scheduler = Self;
     ///
     ///     fn start<R>(self, receiver: R)
     ///     where
-    ///         R: 'static + Send + Receiver<Input=Self::Output>
+    ///         R: 'static + Send + Receiver<Input=Self::Output, Error=Self::Error>
     ///     {
     ///         std::thread::spawn(move || { receiver.set_value(()) });
     ///     }
     ///
-    ///     fn get_scheduler(&self) -> Self::Scheduler {
-    ///         Self
+    ///     fn get_completion_scheduler(&self, _channel: Channel) -> Option<Self::Scheduler> {
+    ///         Some(Self)
     ///     }
     /// }
     ///
@@ -139,6 +346,13 @@ pub trait SenderExt: 'static + sealed::Sealed + Sender + Sized {
     /// ```
     ///
     /// But since the scheduler isn't deterministic the output may/will vary from run to run.
+    ///
+    /// If `self` has no completion scheduler, the functions are run on the
+    /// [`PreferredScheduler`](crate::env::PreferredScheduler) in the receiver's
+    /// [`env`](crate::env), if there is one, and inline otherwise.
+    ///
+    /// A panic in `func` unwinds through the scheduler thread it runs on, use
+    /// [`bulk_catch_unwind()`](SenderExt::bulk_catch_unwind) to report it as an error instead.
     #[inline]
     fn bulk<Func, BulkResult>(self, size: usize, func: Func) -> Bulk<Self, Func>
     where
@@ -147,6 +361,88 @@ pub trait SenderExt: 'static + sealed::Sealed + Sender + Sized {
         Bulk::new(self, size, func)
     }
 
+    /// Like [`bulk()`](SenderExt::bulk), but sends a
+    /// [`Panicked`](crate::adaptors::catch_unwind::Panicked) error if `func` panics.
+    ///
+    /// The remaining invocations of `func` still run before the error is sent. Errors from `self`
+    /// are converted using `Into`.
+    #[cfg(feature = "std")]
+    #[inline]
+    fn bulk_catch_unwind<Func, BulkResult>(
+        self,
+        size: usize,
+        func: Func,
+    ) -> CatchUnwind<Bulk<Self, Func>>
+    where
+        Func: Clone + Fn(usize, &Self::Output) -> BulkResult,
+        Self::Error: Into<crate::Error>,
+    {
+        CatchUnwind::new(Bulk::new(self, size, func).catching_panics())
+    }
+
+    /// Returns a sender that sends a [`Panicked`](crate::adaptors::catch_unwind::Panicked) error
+    /// if a closure passed to [`map()`](SenderExt::map), [`and_then()`](SenderExt::and_then),
+    /// [`map_error()`](SenderExt::map_error), [`upon_error()`](SenderExt::upon_error),
+    /// [`upon_cancelled()`](SenderExt::upon_cancelled), [`let_error()`](SenderExt::let_error) or
+    /// [`let_cancelled()`](SenderExt::let_cancelled) in `self` panics.
+    ///
+    /// The panic is caught where the closure runs, so it doesn't unwind through the scheduler
+    /// thread. Errors from `self` are converted using `Into`.
+    ///
+    /// Whether to catch panics is decided once, when an adaptor is started, by looking at the
+    /// [`env`](crate::env) of its receiver. Panics are therefore not caught
+    ///
+    /// * in senders that are started before this receiver is attached, such as the input of
+    ///   [`ensure_started()`](SenderExt::ensure_started) or
+    ///   [`start_detached()`](crate::consumers::start_detached::start_detached),
+    /// * in [`bulk()`](SenderExt::bulk), use [`bulk_catch_unwind()`](SenderExt::bulk_catch_unwind)
+    ///   instead,
+    /// * in work executed directly on a scheduler.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use txrx::adaptors::catch_unwind::Panicked;
+    /// use txrx::SenderExt;
+    ///
+    /// let error = txrx::factories::just(10)
+    ///     .map(|x| if x > 5 { panic!("too large") } else { x })
+    ///     .catch_unwind()
+    ///     .sync_wait()
+    ///     .unwrap_error();
+    /// assert_eq!(error.downcast_ref::<Panicked>().unwrap().message(), "too large");
+    /// ```
+    #[cfg(feature = "std")]
+    #[inline]
+    fn catch_unwind(self) -> CatchUnwind<Self>
+    where
+        Self::Error: Into<crate::Error>,
+    {
+        CatchUnwind::new(self)
+    }
+
+    /// Returns a sender that adds `value` to the environment seen by `self`.
+    ///
+    /// The value is visible to all senders that `self` is built from, see [`env`](crate::env) for
+    /// details. A value added closer to the sender reading it shadows values of the same type
+    /// added further down the pipeline.
+    #[inline]
+    fn with_env<T>(self, value: T) -> WithEnv<Self, T>
+    where
+        T: 'static + Send + Sync,
+    {
+        WithEnv::new(self, value)
+    }
+
+    /// Returns a type-erased version of the sender, see [`AnySender`] for details.
+    #[inline]
+    fn boxed(self) -> AnySender<Self::Output, Self::Error>
+    where
+        Self: Send,
+    {
+        AnySender::new(self)
+    }
+
     /// Starts the sender and returns an awaitable that be used to retrieve the result.
     fn into_awaitable(self) -> Awaitable<Self> {
         Awaitable::new(self)
@@ -0,0 +1,36 @@
+use crate::traits::{Scheduler, Sender};
+use std::time::{Duration, Instant};
+
+/// A scheduler that can run work at a point in time.
+///
+/// ## Examples
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use txrx::timer::TimerScheduler;
+/// use txrx::traits::TimeScheduler;
+/// use txrx::SenderExt;
+///
+/// let mut scheduler = TimerScheduler::new();
+/// let start = Instant::now();
+/// scheduler
+///     .schedule_after(Duration::from_millis(10))
+///     .sync_wait()
+///     .unwrap();
+/// assert!(start.elapsed() >= Duration::from_millis(10));
+/// ```
+pub trait TimeScheduler: Scheduler {
+    type TimedSender: 'static + Send + Sender<Output = ()>;
+
+    /// Returns the current time according to the scheduler's clock.
+    fn now(&self) -> Instant;
+
+    /// Returns a sender that completes on the scheduler once `deadline` has been reached.
+    fn schedule_at(&mut self, deadline: Instant) -> Self::TimedSender;
+
+    /// Returns a sender that completes on the scheduler once `duration` has passed.
+    fn schedule_after(&mut self, duration: Duration) -> Self::TimedSender {
+        let deadline = self.now() + duration;
+        self.schedule_at(deadline)
+    }
+}
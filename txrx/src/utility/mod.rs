@@ -1,6 +1,8 @@
+use crate::env::Env;
+use crate::stop_token::StopToken;
 use crate::traits::receiver::DynReceiver;
-use crate::traits::Receiver;
-use std::cell::UnsafeCell;
+use crate::traits::{Channel, Receiver, Sender};
+use core::cell::UnsafeCell;
 
 pub struct ReceiverRef<R> {
     next: Option<R>,
@@ -14,6 +16,7 @@ impl<R> ReceiverRef<R> {
 
 impl<R: Receiver> DynReceiver for ReceiverRef<R> {
     type Input = R::Input;
+    type Error = R::Error;
 
     fn dyn_set_value(&mut self, value: Self::Input) {
         if let Some(next) = self.next.take() {
@@ -21,7 +24,7 @@ impl<R: Receiver> DynReceiver for ReceiverRef<R> {
         }
     }
 
-    fn dyn_set_error(&mut self, error: crate::Error) {
+    fn dyn_set_error(&mut self, error: Self::Error) {
         if let Some(next) = self.next.take() {
             next.set_error(error);
         }
@@ -32,6 +35,58 @@ impl<R: Receiver> DynReceiver for ReceiverRef<R> {
             next.set_cancelled();
         }
     }
+
+    fn dyn_get_stop_token(&self) -> StopToken {
+        self.next
+            .as_ref()
+            .map(|next| next.get_stop_token())
+            .unwrap_or_default()
+    }
+
+    fn dyn_get_env(&self) -> Env {
+        self.next
+            .as_ref()
+            .map(|next| next.get_env())
+            .unwrap_or_default()
+    }
+}
+
+/// Invokes user closures on behalf of an adaptor.
+///
+/// Panics are caught if the adaptor's receiver sees a
+/// [`catch_unwind()`](crate::SenderExt::catch_unwind) sender in its environment. This is looked
+/// up once, when the receiver of the adaptor is created, and not every time a closure is invoked.
+#[derive(Clone, Default)]
+pub(crate) struct PanicGuard {
+    #[cfg(feature = "std")]
+    sink: Option<crate::adaptors::catch_unwind::PanicSink>,
+}
+
+impl PanicGuard {
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn new(env: &Env) -> Self {
+        Self {
+            sink: env.get().cloned(),
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[inline]
+    pub fn new(_env: &Env) -> Self {
+        Self {}
+    }
+
+    /// Returns `None` if `func` panicked and the panic was caught. The adaptor must then drop its
+    /// receiver without completing it.
+    #[inline]
+    pub fn call<T>(&self, func: impl FnOnce() -> T) -> Option<T> {
+        #[cfg(feature = "std")]
+        if let Some(sink) = &self.sink {
+            return sink.call(func);
+        }
+        Some(func())
+    }
 }
 
 pub(crate) struct UnsafeSyncCell<T> {
@@ -51,3 +106,28 @@ impl<T> UnsafeSyncCell<T> {
 }
 
 unsafe impl<T> Sync for UnsafeSyncCell<T> {}
+
+/// The completion schedulers of a sender, captured before the sender is started.
+pub(crate) struct CompletionSchedulers<Sched> {
+    value: Option<Sched>,
+    error: Option<Sched>,
+    cancelled: Option<Sched>,
+}
+
+impl<Sched: Clone> CompletionSchedulers<Sched> {
+    pub fn new<S: Sender<Scheduler = Sched>>(sender: &S) -> Self {
+        Self {
+            value: sender.get_completion_scheduler(Channel::Value),
+            error: sender.get_completion_scheduler(Channel::Error),
+            cancelled: sender.get_completion_scheduler(Channel::Cancelled),
+        }
+    }
+
+    pub fn get(&self, channel: Channel) -> Option<Sched> {
+        match channel {
+            Channel::Value => self.value.clone(),
+            Channel::Error => self.error.clone(),
+            Channel::Cancelled => self.cancelled.clone(),
+        }
+    }
+}